@@ -0,0 +1,1605 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::error::{ArithError, EvalError, SessionError};
+use crate::history::History;
+use crate::lexer::{tokenize, Span, Token, TokenKind};
+use crate::number::Number;
+use crate::ops::{Arity, OperatorRegistry};
+use crate::session::Session;
+use crate::value::{Quotation, Term, Value, MAX_NESTING_DEPTH};
+
+// ユーザー定義ワードとクォーテーションの呼び出しを合わせた入れ子の上限
+const MAX_CALL_DEPTH: usize = 256;
+
+/**
+ * RpnCalculator
+ *
+ * 型パラメータ `N` でスタックに積む数値型を選ぶ。
+ * `: name ... ;` で定義したワードと `sto` で保存した変数は、以降の評価でも使える。
+ * `eval_session` で評価した場合はスタックも行をまたいで引き継ぎ、
+ * 行ごとの変更を `undo` と `redo` で取り消し・やり直しできる
+ */
+#[derive(Debug, Clone)]
+pub struct RpnCalculator<N = i32> {
+    config: Config,
+    registry: OperatorRegistry<N>,
+    words: HashMap<String, Quotation<N>>,
+    vars: HashMap<String, Value<N>>,
+    stack: Vec<Value<N>>,
+    history: History<N>,
+    // 直前の演算子が取った先頭の値
+    last_x: Option<Value<N>>,
+    // 直前のエラーが起きた時点のスタック
+    error_stack: Vec<Value<N>>,
+}
+
+impl<N: Number> RpnCalculator<N> {
+    // 設定を受け取ってインスタンスを生成する
+    pub fn new(config: Config) -> Self {
+        Self::with_registry(config, OperatorRegistry::with_builtins())
+    }
+
+    // 設定と演算子の表を受け取ってインスタンスを生成する
+    pub fn with_registry(config: Config, registry: OperatorRegistry<N>) -> Self {
+        Self {
+            config,
+            registry,
+            words: HashMap::new(),
+            vars: HashMap::new(),
+            stack: Vec::new(),
+            history: History::default(),
+            last_x: None,
+            error_stack: Vec::new(),
+        }
+    }
+
+    // 演算子の表を返す
+    pub fn registry(&self) -> &OperatorRegistry<N> {
+        &self.registry
+    }
+
+    // 演算子を追加・削除するために演算子の表を返す
+    pub fn registry_mut(&mut self) -> &mut OperatorRegistry<N> {
+        &mut self.registry
+    }
+
+    // 現在の設定を返す
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // 現在の設定を変更する
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    // 名前順に並べたユーザー定義ワードとその本体
+    pub fn words(&self) -> impl Iterator<Item = (&str, &Quotation<N>)> {
+        let mut words = self
+            .words
+            .iter()
+            .map(|(name, body)| (name.as_str(), body))
+            .collect::<Vec<_>>();
+        words.sort_by_key(|&(name, _)| name);
+        words.into_iter()
+    }
+
+    // 変数の値を返す
+    pub fn var(&self, name: &str) -> Option<&Value<N>> {
+        self.vars.get(name)
+    }
+
+    // 変数に値を保存する
+    pub fn set_var(&mut self, name: &str, value: Value<N>) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    // 名前順に並べた変数とその値
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &Value<N>)> {
+        sorted_vars(&self.vars).into_iter()
+    }
+
+    // 行をパースして計算を実行する。結果が数値でない場合はエラー
+    pub fn eval(&mut self, formula: &str) -> Result<N, EvalError> {
+        match self.eval_value(formula)? {
+            Some(Value::Num(n)) => Ok(n),
+            Some(_) => Err(EvalError::NonNumericResult),
+            None => Err(EvalError::EmptyInput),
+        }
+    }
+
+    // 行をパースして計算を実行し、クォーテーションを含む値を返す。
+    // ワードの定義や変数への保存だけで値が残らなかった場合は None
+    pub fn eval_value(&mut self, formula: &str) -> Result<Option<Value<N>>, EvalError> {
+        let (mut stack, blank) = self.run(formula, Vec::new(), false)?;
+
+        // スタックが空の場合、データが複数残っている場合はエラー。
+        // エラーの表示には前の行ではなくこの行の評価後のスタックを示す
+        match stack.len() {
+            0 if blank => {
+                self.error_stack.clear();
+                Err(EvalError::EmptyInput)
+            }
+            0 => Ok(None),
+            1 => Ok(stack.pop()),
+            count => {
+                self.error_stack = stack;
+                Err(EvalError::LeftoverValues { count })
+            }
+        }
+    }
+
+    // セッションのスタックに対して行を評価する。値はいくつ残ってもよい。
+    // エラーの場合はスタックを評価前の状態に戻す
+    pub fn eval_session(&mut self, formula: &str) -> Result<(), EvalError> {
+        let (stack, _) = self.run(formula, self.stack.clone(), true)?;
+        self.stack = stack;
+        Ok(())
+    }
+
+    // セッションのスタック。末尾が先頭の値
+    pub fn stack(&self) -> &[Value<N>] {
+        &self.stack
+    }
+
+    // 直前の評価がエラーになった時点のスタック。演算子の失敗では取り出した値も含む
+    pub fn error_stack(&self) -> &[Value<N>] {
+        &self.error_stack
+    }
+
+    // セッションのスタックを空にして、残っていた値を返す
+    pub fn take_stack(&mut self) -> Vec<Value<N>> {
+        std::mem::take(&mut self.stack)
+    }
+
+    // スタック、変数、ユーザー定義ワード、設定をファイルに保存する
+    pub fn save_session(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        Session::new(&self.config, self.words(), self.vars(), &self.stack).save(path.as_ref())
+    }
+
+    // 保存したセッションを読み込んで状態を置き換える。取り消しの履歴は捨てる
+    pub fn load_session(&mut self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let session = Session::load(path.as_ref())?;
+        self.replace(session);
+        Ok(())
+    }
+
+    // 設定を置き換える。スタック、変数、ワードの値は新しい設定で読み直すので、
+    // 固定小数点数の桁数などもその設定に揃う。取り消しの履歴は捨てる
+    pub fn set_config(&mut self, config: Config) -> Result<(), SessionError> {
+        let session = Session::new(&config, self.words(), self.vars(), &self.stack).reread()?;
+        self.config.verbose = config.verbose;
+        self.replace(session);
+        Ok(())
+    }
+
+    // セッションの内容で状態を置き換える
+    fn replace(&mut self, session: Session<N>) {
+        self.stack = session.stack;
+        self.vars = session.vars.into_iter().collect();
+        self.history = History::default();
+        self.restore(session.config, session.words);
+    }
+
+    // 読み込んだ設定とワードに置き換える。出力の詳しさは保存した時点のものではなく現在の設定に従う
+    fn restore(&mut self, mut config: Config, words: Vec<(String, Quotation<N>)>) {
+        config.verbose = self.config.verbose;
+        self.config = config;
+        self.words = words.into_iter().collect();
+        self.last_x = None;
+    }
+
+    // 与えたスタックに対して行を評価し、評価後のスタックと行が空だったかどうかを返す。
+    // セッションの場合はスタックの変更を履歴に記録する
+    fn run(
+        &mut self,
+        formula: &str,
+        stack: Vec<Value<N>>,
+        session: bool,
+    ) -> Result<(Vec<Value<N>>, bool), EvalError> {
+        // 文字列をトークンに分けてプログラムとワードの定義を組み立てる
+        let parsed = parse(formula, &self.config).and_then(|(program, definitions)| {
+            for def in definitions {
+                self.define(def)?;
+            }
+            Ok(program)
+        });
+        let program = match parsed {
+            Ok(program) => program,
+            Err(e) => {
+                self.error_stack = stack;
+                return Err(e);
+            }
+        };
+        // 注釈だけの行は空行として扱わない
+        let blank = formula.trim().is_empty();
+
+        // 計算を実行する。変数への保存はエラーになっても残す
+        let vars = std::mem::take(&mut self.vars);
+        let history = match session {
+            true => std::mem::take(&mut self.history),
+            false => History::default(),
+        };
+        let last_x = self.last_x.take();
+        let mut ctx = Context::new(self, stack, vars, history, last_x);
+        let res = ctx.run(&program);
+        let Context {
+            stack,
+            vars,
+            history,
+            base,
+            last_x,
+            loaded,
+            ..
+        } = ctx;
+        self.vars = vars;
+        self.last_x = last_x;
+        if let Some(session) = loaded {
+            self.restore(session.config, session.words);
+        }
+
+        if session {
+            self.history = history;
+            match (&res, base) {
+                (Ok(()), Some(base)) => self.history.record(&base, &stack),
+                (Ok(()), None) => self.history.record(&self.stack, &stack),
+                // 取り消し・やり直しの結果はエラーになっても残す
+                (Err(_), Some(base)) => self.stack = base,
+                (Err(_), None) => {}
+            }
+        }
+        if let Err(e) = res {
+            self.error_stack = stack;
+            return Err(e);
+        }
+        Ok((stack, blank))
+    }
+
+    // ワードを辞書に登録する。組み込みの演算子と同じ名前は強制しない限りエラー
+    fn define(&mut self, def: Definition<N>) -> Result<(), EvalError> {
+        if !def.force && self.registry.get(&def.name).is_some() {
+            return Err(EvalError::RedefineBuiltin {
+                token: def.name,
+                span: def.span,
+            });
+        }
+        self.words.insert(def.name, def.body);
+        Ok(())
+    }
+}
+
+/**
+ * 式をクォーテーションの入れ子とワードの定義に組み立てる
+ *
+ * 数値のトークンはこの時点で設定に従って解釈する
+ */
+pub(crate) fn parse<N: Number>(
+    formula: &str,
+    config: &Config,
+) -> Result<(Quotation<N>, Vec<Definition<N>>), EvalError> {
+    let mut definitions = Vec::new();
+    let mut frames = vec![Frame::new(Span::default(), None)];
+    let mut tokens = tokenize(formula)?.into_iter();
+    while let Some(Token { kind, text, span }) = tokens.next() {
+        let term = match kind {
+            // 文字列と `'name` の変数名はそのまま積む
+            TokenKind::Str(s) => Term::Push(Value::Str(s)),
+            TokenKind::Symbol(name) => Term::Push(Value::Symbol(name)),
+            TokenKind::Word => match text {
+                "[" => {
+                    // 入れ子の深いクォーテーションは評価や破棄でスタックを使い切るので、
+                    // 組み立てる前に止める
+                    if frames.len() > MAX_NESTING_DEPTH {
+                        return Err(EvalError::NestingLimit {
+                            token: text.to_string(),
+                            span,
+                        });
+                    }
+                    frames.push(Frame::new(span, None));
+                    continue;
+                }
+                "]" => {
+                    // 対応する `[` がない
+                    if frames.len() == 1 || frames.last().unwrap().definition.is_some() {
+                        return Err(EvalError::UnbalancedBracket {
+                            token: text.to_string(),
+                            span,
+                        });
+                    }
+                    let frame = frames.pop().unwrap();
+                    let quote = Quotation::new(frame.terms).map_err(|e| e.at(text, span))?;
+                    Term::Push(Value::Quote(quote))
+                }
+                ":" | ":!" => {
+                    // 定義はクォーテーションや他の定義の中には書けない
+                    if frames.len() > 1 {
+                        return Err(EvalError::BadDefinition {
+                            token: text.to_string(),
+                            span,
+                        });
+                    }
+                    let name = tokens.next().ok_or_else(|| EvalError::BadDefinition {
+                        token: text.to_string(),
+                        span,
+                    })?;
+                    if name.kind != TokenKind::Word
+                        || matches!(name.text, "[" | "]" | ":" | ":!" | ";")
+                        || N::parse(name.text, config).is_some()
+                        || N::out_of_range(name.text, config)
+                    {
+                        return Err(EvalError::BadDefinition {
+                            token: name.text.to_string(),
+                            span: name.span,
+                        });
+                    }
+                    let force = text == ":!";
+                    frames.push(Frame::new(name.span, Some((name.text.to_string(), force))));
+                    continue;
+                }
+                ";" => {
+                    // 定義の外、またはクォーテーションを閉じる前の `;`
+                    if frames.len() != 2 || frames[1].definition.is_none() {
+                        return Err(EvalError::BadDefinition {
+                            token: text.to_string(),
+                            span,
+                        });
+                    }
+                    let frame = frames.pop().unwrap();
+                    let (name, force) = frame.definition.unwrap();
+                    definitions.push(Definition {
+                        name,
+                        span: frame.span,
+                        force,
+                        body: Quotation::program(frame.terms),
+                    });
+                    continue;
+                }
+                _ => match N::parse(text, config) {
+                    Some(x) => Term::Push(Value::Num(x)),
+                    // 数値の書式だが数値型に収まらないリテラル
+                    None if N::out_of_range(text, config) => {
+                        return Err(EvalError::Overflow {
+                            token: text.to_string(),
+                            span,
+                        })
+                    }
+                    None => Term::Word {
+                        name: text.to_string(),
+                        span,
+                    },
+                },
+            },
+        };
+        frames.last_mut().unwrap().terms.push(term);
+    }
+
+    // 閉じられていない `[` または `;` のない定義がある
+    if frames.len() > 1 {
+        let frame = frames.pop().unwrap();
+        return Err(match frame.definition {
+            Some((name, _)) => EvalError::BadDefinition {
+                token: name,
+                span: frame.span,
+            },
+            None => EvalError::UnbalancedBracket {
+                token: "[".to_string(),
+                span: frame.span,
+            },
+        });
+    }
+    let program = Quotation::program(frames.pop().unwrap().terms);
+    Ok((program, definitions))
+}
+
+/**
+ * 組み立て中のクォーテーションまたはワードの定義
+ */
+struct Frame<N> {
+    terms: Vec<Term<N>>,
+    // `[` またはワード名の位置
+    span: Span,
+    // ワードの定義の場合は名前と強制するかどうか
+    definition: Option<(String, bool)>,
+}
+
+impl<N> Frame<N> {
+    fn new(span: Span, definition: Option<(String, bool)>) -> Self {
+        Self {
+            terms: Vec::new(),
+            span,
+            definition,
+        }
+    }
+}
+
+/**
+ * `: name ... ;` によるワードの定義
+ */
+pub(crate) struct Definition<N> {
+    name: String,
+    span: Span,
+    // `:!` で組み込みの演算子の置き換えを強制する
+    force: bool,
+    body: Quotation<N>,
+}
+
+/**
+ * 変数を名前順に並べる
+ */
+fn sorted_vars<N>(vars: &HashMap<String, Value<N>>) -> Vec<(&str, &Value<N>)> {
+    let mut vars = vars
+        .iter()
+        .map(|(name, value)| (name.as_str(), value))
+        .collect::<Vec<_>>();
+    vars.sort_by_key(|&(name, _)| name);
+    vars
+}
+
+/**
+ * 評価中の状態
+ *
+ * 演算子はこれを通して設定を参照し、残りのスタックと変数を操作してクォーテーションを評価する
+ */
+pub struct Context<'a, N> {
+    calc: &'a RpnCalculator<N>,
+    stack: Vec<Value<N>>,
+    // 評価の間だけ計算機から預かる変数
+    vars: HashMap<String, Value<N>>,
+    // 実行中のユーザー定義ワードの入れ子の深さ
+    depth: usize,
+    // コンビネータから評価中のクォーテーションの入れ子の深さ
+    calls: usize,
+    // 評価の間だけ計算機から預かるスタックの履歴
+    history: History<N>,
+    // この行で取り消し・やり直しをした場合の、履歴が指しているスタック
+    base: Option<Vec<Value<N>>>,
+    last_x: Option<Value<N>>,
+    // `load` で読み込んだ、行の評価を終えてから反映する設定とワード
+    loaded: Option<Session<N>>,
+}
+
+impl<'a, N: Number> Context<'a, N> {
+    // 与えたスタックで評価を始める
+    fn new(
+        calc: &'a RpnCalculator<N>,
+        stack: Vec<Value<N>>,
+        vars: HashMap<String, Value<N>>,
+        history: History<N>,
+        last_x: Option<Value<N>>,
+    ) -> Self {
+        Self {
+            calc,
+            stack,
+            vars,
+            depth: 0,
+            calls: 0,
+            history,
+            base: None,
+            last_x,
+            loaded: None,
+        }
+    }
+
+    // 現在の設定を返す
+    pub fn config(&self) -> &Config {
+        &self.calc.config
+    }
+
+    // 演算子が取り出した分を除いたスタック
+    pub fn stack(&mut self) -> &mut Vec<Value<N>> {
+        &mut self.stack
+    }
+
+    // 変数の値を返す
+    pub fn var(&self, name: &str) -> Result<&Value<N>, ArithError> {
+        self.vars
+            .get(name)
+            .ok_or_else(|| ArithError::UndefinedVariable(name.to_string()))
+    }
+
+    // 変数に値を保存する
+    pub fn set_var(&mut self, name: String, value: Value<N>) {
+        self.vars.insert(name, value);
+    }
+
+    // 名前順に並べた変数とその値
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &Value<N>)> {
+        sorted_vars(&self.vars).into_iter()
+    }
+
+    // 直前の行の変更を取り消す。この行でそれまでに行った変更も捨てる
+    pub fn undo(&mut self) -> Result<(), ArithError> {
+        let base = self.base.as_deref().unwrap_or(&self.calc.stack);
+        let stack = self.history.undo(base).ok_or(ArithError::NoHistory)?;
+        self.stack = stack.clone();
+        self.base = Some(stack);
+        Ok(())
+    }
+
+    // 取り消した変更をやり直す。この行でそれまでに行った変更は捨てる
+    pub fn redo(&mut self) -> Result<(), ArithError> {
+        let base = self.base.as_deref().unwrap_or(&self.calc.stack);
+        let stack = self.history.redo(base).ok_or(ArithError::NoHistory)?;
+        self.stack = stack.clone();
+        self.base = Some(stack);
+        Ok(())
+    }
+
+    // 直前の演算子が取った先頭の値
+    pub fn last_x(&self) -> Result<&Value<N>, ArithError> {
+        self.last_x.as_ref().ok_or(ArithError::NoHistory)
+    }
+
+    // 現在のスタック、変数、ワード、設定をファイルに保存する
+    pub fn save_session(&self, path: &Path) -> Result<(), ArithError> {
+        let words = self.calc.words();
+        Session::new(self.config(), words, self.vars(), &self.stack)
+            .save(path)
+            .map_err(ArithError::Session)
+    }
+
+    // 保存したセッションを読み込む。スタックと変数はすぐに置き換え、
+    // 設定とワードは行の評価を終えてから反映する。取り消しの履歴は捨てる
+    pub fn load_session(&mut self, path: &Path) -> Result<(), ArithError> {
+        let mut session = Session::load(path).map_err(ArithError::Session)?;
+        self.stack = std::mem::take(&mut session.stack);
+        self.base = Some(self.stack.clone());
+        self.history = History::default();
+        self.vars = session.vars.drain(..).collect();
+        self.loaded = Some(session);
+        Ok(())
+    }
+
+    // クォーテーションを現在のスタックに対して評価する
+    pub fn call(&mut self, quote: &Quotation<N>) -> Result<(), ArithError> {
+        if self.depth + self.calls >= MAX_CALL_DEPTH {
+            return Err(ArithError::RecursionLimit);
+        }
+        self.calls += 1;
+        let res = self.run(quote);
+        self.calls -= 1;
+        res.map_err(|e| ArithError::Nested(Box::new(e)))
+    }
+
+    // 項を順に評価する
+    fn run(&mut self, quote: &Quotation<N>) -> Result<(), EvalError> {
+        let mut terms = quote.terms().iter().peekable();
+        while let Some(term) = terms.next() {
+            match term {
+                // 数値とクォーテーションはスタックに保存
+                Term::Push(v) => self.stack.push(v.clone()),
+                // `rate rcl` の `rate` は `'rate` と同じく変数名として積む
+                Term::Word { name, .. } if self.is_bare_var_name(name, terms.peek()) => {
+                    self.stack.push(Value::Symbol(name.clone()))
+                }
+                Term::Word { name, span } => self.exec(name, *span)?,
+            }
+
+            // `-v` オプションが指定されている場合は、この時点でのトークンとスタックの状態を出力
+            if self.config().verbose {
+                println!("{:?} {:?}", term, self.stack);
+            }
+        }
+        Ok(())
+    }
+
+    // 名前からユーザー定義ワードまたは演算子を引いて適用する
+    fn exec(&mut self, token: &str, span: Span) -> Result<(), EvalError> {
+        let calc = self.calc;
+        if let Some(body) = calc.words.get(token) {
+            return self.call_word(token, span, body);
+        }
+        let Some(op) = calc.registry.get(token) else {
+            return self.access_var(token, span);
+        };
+
+        // 演算子が必要とする個数の値がスタックにあることを確かめてから取り出す
+        let stack = &mut self.stack;
+        let args = match op.arity() {
+            Arity::Fixed(n) if stack.len() >= n => stack.split_off(stack.len() - n),
+            Arity::Variadic(min) if stack.len() >= min => std::mem::take(stack),
+            _ => {
+                return Err(EvalError::StackUnderflow {
+                    token: token.to_string(),
+                    span,
+                })
+            }
+        };
+
+        // 演算子を適用し、結果をスタックに保存
+        if let Some(x) = args.last() {
+            self.last_x = Some(x.clone());
+        }
+        // 失敗した時点のスタックを示せるように、取り出した値を控えておく
+        let operands = args.clone();
+        match op.apply(args, self) {
+            Ok(res) => {
+                self.stack.extend(res);
+                Ok(())
+            }
+            // クォーテーションの中で失敗した場合はその時点のスタックのままにする
+            Err(e @ ArithError::Nested(_)) => Err(e.at(token, span)),
+            Err(e) => {
+                self.stack.extend(operands);
+                Err(e.at(token, span))
+            }
+        }
+    }
+
+    // 組み込みの `rcl` または `sto` の直前にある、ワードでも演算子でもない名前かどうか
+    fn is_bare_var_name(&self, name: &str, next: Option<&&Term<N>>) -> bool {
+        let Some(Term::Word { name: next, .. }) = next else {
+            return false;
+        };
+        let calc = self.calc;
+        matches!(next.as_str(), "rcl" | "sto")
+            && !calc.words.contains_key(next)
+            && !calc.words.contains_key(name)
+            && calc.registry.get(name).is_none()
+            && !name.starts_with(['=', '$'])
+    }
+
+    // `=name` で変数に保存し、`$name` で変数の値を積む
+    fn access_var(&mut self, token: &str, span: Span) -> Result<(), EvalError> {
+        if let Some(name) = token.strip_prefix('=').filter(|name| !name.is_empty()) {
+            let value = self.stack.pop().ok_or_else(|| EvalError::StackUnderflow {
+                token: token.to_string(),
+                span,
+            })?;
+            self.set_var(name.to_string(), value);
+            Ok(())
+        } else if let Some(name) = token.strip_prefix('$').filter(|name| !name.is_empty()) {
+            let value = self.var(name).map_err(|e| e.at(token, span))?.clone();
+            self.stack.push(value);
+            Ok(())
+        } else {
+            // 数値でも演算子でもないトークン
+            Err(EvalError::UnknownToken {
+                token: token.to_string(),
+                span,
+            })
+        }
+    }
+
+    // ユーザー定義ワードの本体を評価する
+    fn call_word(&mut self, token: &str, span: Span, body: &Quotation<N>) -> Result<(), EvalError> {
+        // 無限の再帰でスタックを使い果たさないように深さを制限する
+        if self.depth + self.calls >= MAX_CALL_DEPTH {
+            return Err(EvalError::RecursionLimit {
+                token: token.to_string(),
+                span,
+            });
+        }
+        self.depth += 1;
+        let res = self.run(body);
+        self.depth -= 1;
+
+        // 本体の中の位置は定義した行を指すので、最も外側の呼び出し位置を添える
+        res.map_err(|e| match self.depth {
+            0 => EvalError::InWord {
+                word: token.to_string(),
+                span,
+                source: Box::new(e),
+            },
+            _ => e,
+        })
+    }
+}
+
+impl<N: Number> Default for RpnCalculator<N> {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Angle, Arithmetic};
+    use crate::number::{BigInt, Complex, Decimal, Rational};
+
+    #[test]
+    fn test_ok() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("5").unwrap(), 5);
+        assert_eq!(calc.eval("50").unwrap(), 50);
+        assert_eq!(calc.eval("-50").unwrap(), -50);
+
+        assert_eq!(calc.eval("2 3 +").unwrap(), 5);
+        assert_eq!(calc.eval("2 3 *").unwrap(), 6);
+        assert_eq!(calc.eval("2 3 -").unwrap(), -1);
+        assert_eq!(calc.eval("2 3 /").unwrap(), 0);
+        assert_eq!(calc.eval("2 3 %").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(
+            calc.eval("1000 1000 * 1000 * 10 *"),
+            Err(EvalError::Overflow {
+                token: "*".to_string(),
+                span: Span::new(22, 23, 23)
+            })
+        );
+        assert_eq!(
+            calc.eval("1 0 /"),
+            Err(EvalError::DivisionByZero {
+                token: "/".to_string(),
+                span: Span::new(4, 5, 5)
+            })
+        );
+        assert!(calc.eval("1 0 %").is_err());
+
+        let mut calc = RpnCalculator::<i32>::new(Config {
+            arithmetic: Arithmetic::Wrapping,
+            ..Config::default()
+        });
+        assert_eq!(calc.eval("2147483647 1 +").unwrap(), i32::MIN);
+        assert_eq!(calc.eval("-2147483648 -1 /").unwrap(), i32::MIN);
+        assert!(calc.eval("1 0 /").is_err());
+
+        let mut calc = RpnCalculator::<i32>::new(Config {
+            arithmetic: Arithmetic::Saturating,
+            ..Config::default()
+        });
+        assert_eq!(calc.eval("2147483647 1 +").unwrap(), i32::MAX);
+        assert_eq!(calc.eval("1000000 -1000000 *").unwrap(), i32::MIN);
+    }
+
+    #[test]
+    fn test_backends() {
+        let mut calc = RpnCalculator::<i64>::new(Config::default());
+        assert_eq!(
+            calc.eval("1000 1000 * 1000 * 10 *").unwrap(),
+            10_000_000_000
+        );
+        assert!(calc.eval("9223372036854775807 1 +").is_err());
+        assert_eq!(calc.eval("0xFFFFFFFF").unwrap(), 0xFFFF_FFFF);
+
+        // 数値型に収まらないリテラルは未知のトークンではなくオーバーフロー
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(
+            calc.eval("1 99999999999 +").unwrap_err(),
+            EvalError::Overflow {
+                token: "99999999999".to_string(),
+                span: Span::new(2, 13, 3)
+            }
+        );
+        assert_eq!(
+            calc.eval("0xFFFFFFFF").unwrap_err(),
+            EvalError::Overflow {
+                token: "0xFFFFFFFF".to_string(),
+                span: Span::new(0, 10, 1)
+            }
+        );
+        assert!(matches!(
+            calc.eval(": 99999999999 1 ;").unwrap_err(),
+            EvalError::BadDefinition { .. }
+        ));
+
+        let mut calc = RpnCalculator::<i128>::new(Config::default());
+        assert_eq!(
+            calc.eval("9223372036854775807 1 +").unwrap(),
+            9_223_372_036_854_775_808
+        );
+
+        let mut calc = RpnCalculator::<BigInt>::new(Config::default());
+        assert_eq!(
+            calc.eval("170141183460469231731687303715884105727 2 *")
+                .unwrap()
+                .to_string(),
+            "340282366920938463463374607431768211454"
+        );
+        assert_eq!(calc.eval("-7 2 %").unwrap().to_string(), "-1");
+        assert!(calc.eval("1 0 /").is_err());
+        assert!(matches!(
+            calc.eval("0x1FFFFFFFFFFFFFFFF").unwrap_err(),
+            EvalError::Overflow { .. }
+        ));
+    }
+
+    #[test]
+    fn test_float() {
+        let mut calc = RpnCalculator::<f64>::new(Config::default());
+        assert_eq!(calc.eval("1 4 /").unwrap(), 0.25);
+        assert_eq!(calc.eval("1.5e-3 2 *").unwrap(), 0.003);
+        assert_eq!(calc.eval("1 0 /").unwrap(), f64::INFINITY);
+        assert!(calc.eval("0 0 /").unwrap().is_nan());
+        assert!(calc.eval("inf -inf +").unwrap().is_nan());
+        assert!(calc.eval("1 +").is_err());
+    }
+
+    #[test]
+    fn test_rational() {
+        let mut calc = RpnCalculator::<Rational>::new(Config::default());
+        assert_eq!(calc.eval("1 3 / 1 6 / +").unwrap().to_string(), "1/2");
+        assert_eq!(calc.eval("0.1 0.2 + 3/10 -").unwrap().to_string(), "0");
+        assert!(calc.eval("1 0 /").is_err());
+    }
+
+    #[test]
+    fn test_decimal() {
+        let mut calc = RpnCalculator::<Decimal>::new(Config {
+            scale: 4,
+            ..Config::default()
+        });
+        assert_eq!(calc.eval("19.99 3 *").unwrap().to_string(), "59.9700");
+        assert_eq!(calc.eval("100 3 /").unwrap().to_string(), "33.3333");
+        assert!(calc.eval("1 0 /").is_err());
+    }
+
+    #[test]
+    fn test_complex() {
+        let mut calc = RpnCalculator::<Complex>::new(Config::default());
+        assert_eq!(calc.eval("1+2i 3-i *").unwrap(), Complex::new(5.0, 5.0));
+        assert_eq!(calc.eval("3 4 cplx abs").unwrap(), Complex::new(5.0, 0.0));
+        assert_eq!(calc.eval("3+4i conj im").unwrap(), Complex::new(-4.0, 0.0));
+        assert_eq!(
+            calc.eval("1 2 %"),
+            Err(EvalError::Unsupported {
+                token: "%".to_string(),
+                span: Span::new(4, 5, 5)
+            })
+        );
+
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("-5 abs").unwrap(), 5);
+        assert!(calc.eval("-2147483648 abs").is_err());
+        assert!(calc.eval("5 conj").is_err());
+        assert!(calc.eval("abs").is_err());
+    }
+
+    #[test]
+    fn test_stack_words() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("3 dup *").unwrap(), 9);
+        assert_eq!(calc.eval("1 2 drop").unwrap(), 1);
+        assert_eq!(calc.eval("1 2 swap -").unwrap(), 1);
+        assert_eq!(calc.eval("1 2 over - -").unwrap(), 0);
+        assert_eq!(calc.eval("1 2 3 rot - -").unwrap(), 0);
+        assert_eq!(calc.eval("1 2 3 -rot - -").unwrap(), 4);
+        assert_eq!(calc.eval("1 2 nip").unwrap(), 2);
+        assert_eq!(calc.eval("1 2 tuck - -").unwrap(), 3);
+        assert_eq!(calc.eval("10 20 30 2 pick nip nip nip").unwrap(), 10);
+        assert_eq!(calc.eval("10 20 30 2 roll - -").unwrap(), 0);
+        assert_eq!(calc.eval("1 2 3 clear 7").unwrap(), 7);
+        assert_eq!(calc.eval("1 2 3 depth nip nip nip").unwrap(), 3);
+        assert_eq!(calc.eval("depth").unwrap(), 0);
+
+        assert_eq!(
+            calc.eval("1 2 rot"),
+            Err(EvalError::StackUnderflow {
+                token: "rot".to_string(),
+                span: Span::new(4, 7, 5)
+            })
+        );
+        assert!(calc.eval("1 2 2 pick").is_err());
+        assert_eq!(
+            calc.eval("1 -1 roll"),
+            Err(EvalError::Domain {
+                token: "roll".to_string(),
+                span: Span::new(5, 9, 6)
+            })
+        );
+        assert!(RpnCalculator::<f64>::new(Config::default())
+            .eval("1 2 0.5 pick")
+            .is_err());
+    }
+
+    #[test]
+    fn test_arity() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("5 neg").unwrap(), -5);
+        assert_eq!(calc.eval("1 2 3 4 sum").unwrap(), 10);
+        assert_eq!(calc.eval("1 2 3 4 prod").unwrap(), 24);
+        assert!(calc.eval("sum").is_err());
+        assert!(calc.eval("4 sqrt").is_err());
+        assert_eq!(
+            RpnCalculator::<f64>::default().eval("2.25 sqrt").unwrap(),
+            1.5
+        );
+
+        // 不明なトークンはスタックの値を消費せずにエラーになる
+        assert_eq!(
+            calc.eval("1 foo"),
+            Err(EvalError::UnknownToken {
+                token: "foo".to_string(),
+                span: Span::new(2, 5, 3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_number_theory() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("2 10 ^").unwrap(), 1024);
+        assert_eq!(calc.eval("-3 3 pow").unwrap(), -27);
+        assert_eq!(calc.eval("2 30 ^").unwrap(), 1 << 30);
+        assert_eq!(
+            calc.eval("2 31 ^"),
+            Err(EvalError::Overflow {
+                token: "^".to_string(),
+                span: Span::new(5, 6, 6)
+            })
+        );
+        assert_eq!(
+            calc.eval("2 -1 ^"),
+            Err(EvalError::Domain {
+                token: "^".to_string(),
+                span: Span::new(5, 6, 6)
+            })
+        );
+        assert_eq!(calc.eval("17 isqrt").unwrap(), 4);
+        assert!(calc.eval("-1 isqrt").is_err());
+        assert_eq!(calc.eval("12 18 gcd").unwrap(), 6);
+        assert_eq!(calc.eval("4 6 lcm").unwrap(), 12);
+        assert_eq!(calc.eval("0 fact").unwrap(), 1);
+        assert_eq!(calc.eval("12 fact").unwrap(), 479001600);
+        assert!(calc.eval("13 fact").is_err());
+        assert!(calc.eval("-1 fact").is_err());
+        assert_eq!(calc.eval("5 2 ncr").unwrap(), 10);
+        assert_eq!(calc.eval("5 2 npr").unwrap(), 20);
+        assert!(calc.eval("2 5 ncr").is_err());
+        assert_eq!(calc.eval("4 13 497 modpow").unwrap(), 445);
+        assert!(calc.eval("4 13 0 modpow").is_err());
+        assert_eq!(calc.eval("3 11 modinv").unwrap(), 4);
+        assert!(calc.eval("6 9 modinv").is_err());
+        assert_eq!(calc.eval("97 isprime").unwrap(), 1);
+        assert_eq!(calc.eval("91 isprime").unwrap(), 0);
+
+        let mut calc = RpnCalculator::<BigInt>::new(Config::default());
+        assert_eq!(
+            calc.eval("30 fact").unwrap().to_string(),
+            "265252859812191058636308480000000"
+        );
+        assert_eq!(
+            calc.eval("2 100 ^ isqrt").unwrap().to_string(),
+            "1125899906842624"
+        );
+        // 結果が大きすぎるべき乗は計算を始める前にエラーにする
+        assert!(matches!(
+            calc.eval("2 10000000000 ^").unwrap_err(),
+            EvalError::Overflow { .. }
+        ));
+        assert_eq!(calc.eval("1 10000000000 ^").unwrap().to_string(), "1");
+
+        let mut calc = RpnCalculator::<f64>::new(Config::default());
+        assert_eq!(calc.eval("2 0.5 ^").unwrap(), 2f64.sqrt());
+        assert_eq!(calc.eval("5 fact").unwrap(), 120.0);
+        assert!(calc.eval("2.5 fact").is_err());
+        // 無限大になる階乗はエラーにする
+        assert!(matches!(
+            calc.eval("171 fact").unwrap_err(),
+            EvalError::Overflow { .. }
+        ));
+        assert!(calc.eval("1e15 fact").is_err());
+
+        let mut calc = RpnCalculator::<Rational>::new(Config::default());
+        assert_eq!(calc.eval("2/3 -2 ^").unwrap().to_string(), "9/4");
+        assert!(calc.eval("0 -1 ^").is_err());
+        assert!(calc.eval("2 1/2 ^").is_err());
+        assert!(calc.eval("1/3 10000000000 ^").is_err());
+
+        let mut calc = RpnCalculator::<Decimal>::new(Config::default());
+        assert!(calc.eval("1.5 10000000000 ^").is_err());
+        // 1 に近い底でも結果の仮数は伸びるので、計算を始める前にエラーにする
+        assert!(matches!(
+            calc.eval("1.01 100000000 ^").unwrap_err(),
+            EvalError::Overflow { .. }
+        ));
+        assert_eq!(calc.eval("1.01 2 ^").unwrap().to_string(), "1.02");
+        assert_eq!(calc.eval("-1 100000001 ^").unwrap().to_string(), "-1.00");
+        assert_eq!(calc.eval("0.5 10000000000 ^").unwrap().to_string(), "0.00");
+    }
+
+    #[test]
+    fn test_scientific() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        let mut calc = RpnCalculator::<f64>::new(Config::default());
+        assert!(close(calc.eval("pi 2 / sin").unwrap(), 1.0));
+        assert!(close(
+            calc.eval("1 1 atan2 4 *").unwrap(),
+            std::f64::consts::PI
+        ));
+        assert!(close(calc.eval("e ln").unwrap(), 1.0));
+        assert!(close(calc.eval("1000 log10").unwrap(), 3.0));
+        assert!(close(calc.eval("1024 log2").unwrap(), 10.0));
+        assert!(close(calc.eval("0 exp").unwrap(), 1.0));
+        assert!(close(calc.eval("tau pi /").unwrap(), 2.0));
+        assert_eq!(calc.eval("-2.5 floor").unwrap(), -3.0);
+        assert_eq!(calc.eval("-2.5 ceil").unwrap(), -2.0);
+        assert_eq!(calc.eval("-2.5 round").unwrap(), -3.0);
+        assert_eq!(calc.eval("-2.5 trunc").unwrap(), -2.0);
+
+        let mut calc = RpnCalculator::<f64>::new(Config {
+            angle: Angle::Deg,
+            ..Config::default()
+        });
+        assert!(close(calc.eval("30 sin").unwrap(), 0.5));
+        assert!(close(calc.eval("1 atan").unwrap(), 45.0));
+        assert!(close(calc.eval("0 -1 atan2").unwrap(), 180.0));
+
+        let mut calc = RpnCalculator::<f64>::new(Config {
+            angle: Angle::Grad,
+            ..Config::default()
+        });
+        assert!(close(calc.eval("100 sin").unwrap(), 1.0));
+
+        let mut calc = RpnCalculator::<Rational>::new(Config::default());
+        assert_eq!(calc.eval("-7/2 floor").unwrap().to_string(), "-4");
+        assert_eq!(calc.eval("-7/2 round").unwrap().to_string(), "-4");
+        assert_eq!(calc.eval("7/3 ceil").unwrap().to_string(), "3");
+        assert!(calc.eval("2 sin").is_err());
+
+        let mut calc = RpnCalculator::<Decimal>::new(Config::default());
+        assert_eq!(calc.eval("pi").unwrap().to_string(), "3.14");
+        assert_eq!(calc.eval("2.50 round").unwrap().to_string(), "3.00");
+
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert!(calc.eval("pi").is_err());
+        assert_eq!(calc.eval("7 floor").unwrap(), 7);
+    }
+
+    #[test]
+    fn test_logic() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("1 1 =").unwrap(), 1);
+        assert_eq!(calc.eval("1 2 <>").unwrap(), 1);
+        assert_eq!(calc.eval("1 2 <").unwrap(), 1);
+        assert_eq!(calc.eval("2 2 <=").unwrap(), 1);
+        assert_eq!(calc.eval("1 2 >").unwrap(), 0);
+        assert_eq!(calc.eval("1 2 >=").unwrap(), 0);
+        assert_eq!(calc.eval("2 3 and").unwrap(), 1);
+        assert_eq!(calc.eval("0 3 and").unwrap(), 0);
+        assert_eq!(calc.eval("0 -3 or").unwrap(), 1);
+        assert_eq!(calc.eval("5 not").unwrap(), 0);
+        assert_eq!(calc.eval("0 not").unwrap(), 1);
+        assert_eq!(calc.eval("1 10 20 ifte").unwrap(), 10);
+        assert_eq!(calc.eval("0 10 20 ?").unwrap(), 20);
+        assert_eq!(calc.eval("3 7 min").unwrap(), 3);
+        assert_eq!(calc.eval("3 7 max").unwrap(), 7);
+        // 0 以上 100 以下に丸める
+        assert_eq!(calc.eval("150 0 max 100 min").unwrap(), 100);
+        assert_eq!(calc.eval("-5 0 max 100 min").unwrap(), 0);
+        // しきい値を超えたら上限を使う
+        assert_eq!(calc.eval("42 dup 40 > 40 rot ifte").unwrap(), 40);
+
+        let mut calc = RpnCalculator::<Rational>::new(Config::default());
+        assert_eq!(calc.eval("1/3 0.33 >").unwrap().to_string(), "1");
+        assert_eq!(calc.eval("2/4 1/2 =").unwrap().to_string(), "1");
+        assert_eq!(calc.eval("1/3 1/4 min").unwrap().to_string(), "1/4");
+
+        let mut calc = RpnCalculator::<Decimal>::new(Config::default());
+        assert_eq!(calc.eval("1.5 1.25 max").unwrap().to_string(), "1.50");
+        assert_eq!(calc.eval("0.00 not").unwrap().to_string(), "1.00");
+        // 桁数を変える前に積んだ値とも値で比べる
+        calc.eval_session("1.5 1.5 0").unwrap();
+        calc.config_mut().scale = 4;
+        calc.eval_session("not rot 1.5 = rot 1.5 <>").unwrap();
+        let flags = calc
+            .take_stack()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(flags, ["1.0000", "1.0000", "0.0000"]);
+
+        let mut calc = RpnCalculator::<f64>::new(Config::default());
+        assert_eq!(calc.eval("0.1 0.2 + 0.3 >").unwrap(), 1.0);
+        assert!(calc.eval("0 0 / 1 <").is_err());
+        assert_eq!(calc.eval("0 0 / dup =").unwrap(), 0.0);
+        assert_eq!(calc.eval("0 0 / dup <>").unwrap(), 1.0);
+
+        let mut calc = RpnCalculator::<Complex>::new(Config::default());
+        assert_eq!(
+            calc.eval("1 2 cplx 1 2 cplx =").unwrap(),
+            Complex::new(1.0, 0.0)
+        );
+        assert!(calc.eval("1 2 <").is_err());
+    }
+
+    #[test]
+    fn test_quotation() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        let quote = calc.eval_value("[ 1 [2 +] dup ]").unwrap().unwrap();
+        assert_eq!(quote.to_string(), "[ 1 [ 2 + ] dup ]");
+        assert_eq!(calc.eval_value("[]").unwrap().unwrap().to_string(), "[ ]");
+        assert_eq!(calc.eval("[1 2] drop 3").unwrap(), 3);
+        assert_eq!(calc.eval("[1 +] 2 swap drop").unwrap(), 2);
+        // 書いた位置が違っても同じ項のクォーテーションは等しい
+        assert_eq!(
+            calc.eval_value("[2 +]").unwrap(),
+            calc.eval_value("  [2   +]").unwrap()
+        );
+
+        assert_eq!(
+            calc.eval("[1 2").unwrap_err(),
+            EvalError::UnbalancedBracket {
+                token: "[".to_string(),
+                span: Span::new(0, 1, 1)
+            }
+        );
+        assert_eq!(
+            calc.eval("1 ] 2").unwrap_err(),
+            EvalError::UnbalancedBracket {
+                token: "]".to_string(),
+                span: Span::new(2, 3, 3)
+            }
+        );
+        // 入れ子の上限を超える `[` はパースの時点で止める
+        let nested = format!("{}1{}", "[".repeat(255), "]".repeat(255));
+        assert_eq!(calc.eval(&nested).unwrap_err(), EvalError::NonNumericResult);
+        let deep = "[".repeat(50_000);
+        let err = EvalError::NestingLimit {
+            token: "[".to_string(),
+            span: Span::new(256, 257, 257),
+        };
+        assert_eq!(calc.eval(&deep).unwrap_err(), err);
+        assert_eq!(calc.eval_session(&deep).unwrap_err(), err);
+        assert_eq!(calc.eval("[1]").unwrap_err(), EvalError::NonNumericResult);
+        assert_eq!(
+            calc.eval("[1] 2 +").unwrap_err(),
+            EvalError::TypeMismatch {
+                token: "+".to_string(),
+                span: Span::new(6, 7, 7)
+            }
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("5 1 [10 +] if").unwrap(), 15);
+        assert_eq!(calc.eval("5 0 [10 +] if").unwrap(), 5);
+        assert_eq!(calc.eval("5 dup 3 > [2 *] [2 /] ifelse").unwrap(), 10);
+        assert_eq!(calc.eval("1 10 [2 *] times").unwrap(), 1024);
+        assert_eq!(calc.eval("1 0 [2 *] times").unwrap(), 1);
+        // 100 以上になるまで 2 倍する
+        assert_eq!(calc.eval("3 [dup 100 <] [2 *] while").unwrap(), 192);
+        assert_eq!(calc.eval("0 [1 2 3 4] [+] each").unwrap(), 10);
+        assert_eq!(
+            calc.eval_value("[1 2 3] [dup *] map")
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "[ 1 4 9 ]"
+        );
+        assert_eq!(calc.eval("[1 2 3 4] 1 [*] fold").unwrap(), 24);
+        assert_eq!(calc.eval("[1 2 3] [dup *] map 0 [+] fold").unwrap(), 14);
+        // 入れ子のクォーテーション
+        assert_eq!(calc.eval("0 3 [2 [1 +] times] times").unwrap(), 6);
+        assert_eq!(calc.eval("[[1 2] [3]] 0 [0 [+] fold +] fold").unwrap(), 6);
+
+        // 評価中に組み立てるクォーテーションも `[` と同じ深さで止める
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        let wrap = "[ 0 ] [ drop $q ] map =q";
+        calc.eval_session(&format!("[ ] =q 255 [ {} ] times", wrap))
+            .unwrap();
+        let saved = calc.var("q").unwrap().to_string();
+        assert_eq!(saved.matches('[').count(), 256);
+        assert!(calc.eval_value(&saved).is_ok());
+        assert_eq!(
+            calc.eval_session(wrap).unwrap_err(),
+            EvalError::NestingLimit {
+                token: "map".to_string(),
+                span: Span::new(18, 21, 19)
+            }
+        );
+
+        // クォーテーション内のエラーはそのトークンの位置を指す
+        assert_eq!(
+            calc.eval("1 [0 /] 1 swap if").unwrap_err(),
+            EvalError::DivisionByZero {
+                token: "/".to_string(),
+                span: Span::new(5, 6, 6)
+            }
+        );
+        assert_eq!(
+            calc.eval("1 [foo] 1 swap if").unwrap_err(),
+            EvalError::UnknownToken {
+                token: "foo".to_string(),
+                span: Span::new(3, 6, 4)
+            }
+        );
+        assert_eq!(
+            calc.eval("-1 [1] times").unwrap_err(),
+            EvalError::Domain {
+                token: "times".to_string(),
+                span: Span::new(7, 12, 8)
+            }
+        );
+        assert_eq!(
+            calc.eval("1 2 if").unwrap_err(),
+            EvalError::TypeMismatch {
+                token: "if".to_string(),
+                span: Span::new(4, 6, 5)
+            }
+        );
+        // クォーテーションだけで再帰しても入れ子の深さで止まる
+        assert!(matches!(
+            calc.eval("[dup 1 swap times] dup 1 swap times")
+                .unwrap_err(),
+            EvalError::RecursionLimit { .. }
+        ));
+        assert!(calc.eval("[1 +] [drop] map").is_err());
+    }
+
+    #[test]
+    fn test_definitions() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval_value(": sq dup * ;").unwrap(), None);
+        assert_eq!(calc.eval("3 sq").unwrap(), 9);
+        // 前の行の定義を使える
+        assert_eq!(calc.eval(": cube dup sq * ; 2 cube").unwrap(), 8);
+        assert_eq!(calc.eval("[1 2 3] [cube] map 0 [+] fold").unwrap(), 36);
+        // 同じ行の中で後から定義したワードも使える
+        assert_eq!(calc.eval("4 inc : inc 1 + ;").unwrap(), 5);
+        // ユーザー定義ワードは定義し直せる
+        assert_eq!(calc.eval_value(": sq drop 0 ;"), Ok(None));
+        assert_eq!(calc.eval("3 sq").unwrap(), 0);
+        let names = calc.words().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, ["cube", "inc", "sq"]);
+
+        // 再帰
+        assert_eq!(
+            calc.eval_value(": factorial dup 1 > [dup 1 - factorial *] if ;"),
+            Ok(None)
+        );
+        assert_eq!(calc.eval("5 factorial").unwrap(), 120);
+        assert_eq!(calc.eval_value(": forever forever ;"), Ok(None));
+        assert_eq!(
+            calc.eval("1 forever").unwrap_err(),
+            EvalError::InWord {
+                word: "forever".to_string(),
+                span: Span::new(2, 9, 3),
+                source: Box::new(EvalError::RecursionLimit {
+                    token: "forever".to_string(),
+                    span: Span::new(10, 17, 11)
+                }),
+            }
+        );
+        assert_eq!(
+            calc.eval_value(": deep 1 [0 [deep] [deep] ifelse] times ;"),
+            Ok(None)
+        );
+        assert!(matches!(
+            calc.eval("deep").unwrap_err(),
+            EvalError::InWord { source, .. } if matches!(*source, EvalError::RecursionLimit { .. })
+        ));
+
+        // 組み込みの演算子は `:!` でのみ定義し直せる
+        assert_eq!(
+            calc.eval(": + * ;").unwrap_err(),
+            EvalError::RedefineBuiltin {
+                token: "+".to_string(),
+                span: Span::new(2, 3, 3)
+            }
+        );
+        assert_eq!(calc.eval("2 3 +").unwrap(), 5);
+        assert_eq!(calc.eval(":! + * ; 2 3 +").unwrap(), 6);
+
+        // 本体のエラーは呼び出し位置を添える
+        assert_eq!(calc.eval_value(": bad 0 / ;"), Ok(None));
+        assert_eq!(
+            calc.eval("1 bad").unwrap_err(),
+            EvalError::InWord {
+                word: "bad".to_string(),
+                span: Span::new(2, 5, 3),
+                source: Box::new(EvalError::DivisionByZero {
+                    token: "/".to_string(),
+                    span: Span::new(8, 9, 9)
+                }),
+            }
+        );
+
+        for (formula, token, start) in [
+            (":", ":", 0),
+            (": 1 2 ;", "1", 2),
+            (": foo 1", "foo", 2),
+            ("1 ;", ";", 2),
+            ("[ : foo ; ]", ":", 2),
+            (": foo [ ; ]", ";", 8),
+        ] {
+            assert_eq!(
+                calc.eval(formula).unwrap_err(),
+                EvalError::BadDefinition {
+                    token: token.to_string(),
+                    span: Span::new(start, start + token.len(), start + 1)
+                },
+                "{}",
+                formula
+            );
+        }
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("42 'rate sto 'rate rcl").unwrap(), 42);
+        // 前の行で保存した値を使える
+        assert_eq!(calc.eval("'rate rcl 2 *").unwrap(), 84);
+        assert_eq!(calc.eval("$rate 1 + =rate $rate").unwrap(), 43);
+        assert_eq!(calc.var("rate"), Some(&Value::Num(43)));
+        assert_eq!(calc.eval("0 =i 5 [$i 1 + =i] times $i").unwrap(), 5);
+        // 変数にはクォーテーションも保存できる
+        assert_eq!(calc.eval("[2 *] =double 21 $double 1 swap if").unwrap(), 42);
+
+        calc.set_var("x", Value::Num(7));
+        assert_eq!(
+            calc.eval_value("vars").unwrap().unwrap().to_string(),
+            "[ 'double [ 2 * ] 'i 5 'rate 43 'x 7 ]"
+        );
+        let names = calc.vars().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, ["double", "i", "rate", "x"]);
+
+        // エラーになっても保存した値は残る
+        calc.eval("1 =y 1 0 /").unwrap_err();
+        assert_eq!(calc.eval("$y").unwrap(), 1);
+
+        assert_eq!(
+            calc.eval("$nope").unwrap_err(),
+            EvalError::UndefinedVariable {
+                name: "nope".to_string(),
+                token: "$nope".to_string(),
+                span: Span::new(0, 5, 1)
+            }
+        );
+        assert_eq!(
+            calc.eval("'nope rcl").unwrap_err(),
+            EvalError::UndefinedVariable {
+                name: "nope".to_string(),
+                token: "rcl".to_string(),
+                span: Span::new(6, 9, 7)
+            }
+        );
+        assert_eq!(
+            calc.eval("=z").unwrap_err(),
+            EvalError::StackUnderflow {
+                token: "=z".to_string(),
+                span: Span::new(0, 2, 1)
+            }
+        );
+        assert_eq!(
+            calc.eval("1 2 sto").unwrap_err(),
+            EvalError::TypeMismatch {
+                token: "sto".to_string(),
+                span: Span::new(4, 7, 5)
+            }
+        );
+        assert_eq!(calc.eval("'x").unwrap_err(), EvalError::NonNumericResult);
+
+        // `rcl` と `sto` の直前の名前は `'` を省いて書ける
+        assert_eq!(calc.eval("5 rate sto rate rcl 2 *").unwrap(), 10);
+        assert_eq!(calc.eval("[rate rcl 1 +] 1 swap if").unwrap(), 6);
+        // 演算子の名前は変数名にならない
+        assert_eq!(
+            calc.eval("1 dup sto").unwrap_err(),
+            EvalError::TypeMismatch {
+                token: "sto".to_string(),
+                span: Span::new(6, 9, 7)
+            }
+        );
+        assert_eq!(
+            calc.eval("nope rcl").unwrap_err(),
+            EvalError::UndefinedVariable {
+                name: "nope".to_string(),
+                token: "rcl".to_string(),
+                span: Span::new(5, 8, 6)
+            }
+        );
+
+        // `vars` の結果を保存し続けても、入れ子の上限でエラーになる
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(
+            calc.eval("0 =a 30000 [vars =a] times 1").unwrap_err(),
+            EvalError::NestingLimit {
+                token: "vars".to_string(),
+                span: Span::new(12, 16, 13)
+            }
+        );
+        let saved = calc.var("a").unwrap().to_string();
+        assert_eq!(saved.matches('[').count(), 256);
+    }
+
+    #[test]
+    fn test_literals() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval("1 2 + # 注釈").unwrap(), 3);
+        assert_eq!(calc.eval(": sq ( x -- x*x ) dup * ; 4 sq").unwrap(), 16);
+        assert_eq!(calc.eval_value("# 注釈だけの行").unwrap(), None);
+        assert_eq!(
+            calc.eval_value(r#""a \"b\"""#).unwrap(),
+            Some(Value::Str("a \"b\"".to_string()))
+        );
+        assert_eq!(
+            calc.eval_value(r#"1 '"x y" sto '"x y" rcl"#).unwrap(),
+            Some(Value::Num(1))
+        );
+        assert_eq!(
+            calc.eval(r#""a" 1 +"#).unwrap_err(),
+            EvalError::TypeMismatch {
+                token: "+".to_string(),
+                span: Span::new(6, 7, 7)
+            }
+        );
+
+        // 位置は文字数で数える
+        assert_eq!(
+            calc.eval("\"π\" foo").unwrap_err(),
+            EvalError::UnknownToken {
+                token: "foo".to_string(),
+                span: Span::new(5, 8, 5)
+            }
+        );
+        assert!(matches!(
+            calc.eval("1 \"abc").unwrap_err(),
+            EvalError::Unterminated { span, .. } if span.column == 3
+        ));
+        assert!(matches!(
+            calc.eval(": \"s\" 1 ;").unwrap_err(),
+            EvalError::BadDefinition { span, .. } if span.column == 3
+        ));
+    }
+
+    #[test]
+    fn test_session() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        calc.eval_session("1 2").unwrap();
+        calc.eval_session("3").unwrap();
+        calc.eval_session("+").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(5)]);
+        calc.eval_session("").unwrap();
+
+        // エラーの場合はスタックを戻す
+        assert!(calc.eval_session("10 0 /").is_err());
+        assert!(calc.eval_session("+ +").is_err());
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(5)]);
+
+        // セッションのスタックは eval に影響しない
+        assert_eq!(calc.eval("7").unwrap(), 7);
+        calc.eval_session(": sq dup * ; sq").unwrap();
+        assert_eq!(calc.take_stack(), [Value::Num(1), Value::Num(25)]);
+        assert!(calc.stack().is_empty());
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        calc.eval_session("1 2").unwrap();
+        calc.eval_session("3 +").unwrap();
+        calc.eval_session(": sq dup * ;").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(5)]);
+
+        // 定義だけの行やエラーの行は履歴に残らない
+        assert!(calc.eval_session("0 /").is_err());
+        calc.eval_session("undo").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(2)]);
+        calc.eval_session("undo").unwrap();
+        assert!(calc.stack().is_empty());
+        assert!(matches!(
+            calc.eval_session("undo"),
+            Err(EvalError::NoHistory {
+                span: Span { column: 1, .. },
+                ..
+            })
+        ));
+
+        calc.eval_session("redo redo").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(5)]);
+        assert!(calc.eval_session("redo").is_err());
+
+        // 同じ行でそれまでに積んだ値も捨て、後に続く変更は記録する
+        calc.eval_session("7 undo 9").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(2), Value::Num(9)]);
+        calc.eval_session("undo").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(1), Value::Num(2)]);
+
+        // 取り消しはエラーになっても残る
+        assert!(calc.eval_session("undo +").is_err());
+        assert!(calc.stack().is_empty());
+
+        // lastx は直前の演算子が取った先頭の値
+        calc.eval_session("6 4 - lastx").unwrap();
+        assert_eq!(calc.stack(), [Value::Num(2), Value::Num(4)]);
+        assert_eq!(calc.eval("10 sq lastx +").unwrap(), 110);
+        assert!(RpnCalculator::<i32>::default().eval("lastx").is_err());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join(format!("rpncalc-session-{}", std::process::id()));
+        let mut calc = RpnCalculator::<i32>::new(Config {
+            angle: Angle::Deg,
+            ..Config::default()
+        });
+        calc.eval_session(": sq dup * ; 3 =x 1 2").unwrap();
+        calc.save_session(&path).unwrap();
+
+        let mut other = RpnCalculator::<i32>::default();
+        other.eval_session("9 =y").unwrap();
+        other.load_session(&path).unwrap();
+        assert_eq!(other.stack(), [Value::Num(1), Value::Num(2)]);
+        assert_eq!(other.config().angle, Angle::Deg);
+        assert!(other.var("y").is_none());
+        other.eval_session("$x sq +").unwrap();
+        assert_eq!(other.stack(), [Value::Num(1), Value::Num(11)]);
+        // 読み込む前の変更は取り消せない
+        other.eval_session("undo").unwrap();
+        assert!(other.eval_session("undo").is_err());
+        other.eval_session("redo").unwrap();
+
+        // ワードで保存・読み込みすると、読み込み後の行はそのスタックから続ける
+        let quoted = format!("'{}", path.display());
+        other
+            .eval_session(&format!("{} save 5 =x", quoted))
+            .unwrap();
+        other
+            .eval_session(&format!("drop {} load 100 +", quoted))
+            .unwrap();
+        assert_eq!(other.stack(), [Value::Num(1), Value::Num(111)]);
+        assert_eq!(other.var("x"), Some(&Value::Num(3)));
+        other.eval_session("undo").unwrap();
+        assert_eq!(other.stack(), [Value::Num(1), Value::Num(11)]);
+
+        // 別の種類の数値で保存したセッションは読み込めない
+        let mut rational = RpnCalculator::<Rational>::default();
+        assert!(matches!(
+            rational.load_session(&path),
+            Err(SessionError::NumberKind { .. })
+        ));
+        assert!(matches!(
+            rational.eval_session(&format!("{} load", quoted)),
+            Err(EvalError::Session {
+                source: SessionError::NumberKind { .. },
+                span,
+                ..
+            }) if span.column == quoted.len() + 2
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        // セッションファイルではない既存のファイルは上書きしない
+        std::fs::write(&path, "[package]\n").unwrap();
+        assert_eq!(calc.save_session(&path), Err(SessionError::NotSession));
+        assert!(matches!(
+            calc.eval_session(&format!("{} save", quoted)),
+            Err(EvalError::Session {
+                source: SessionError::NotSession,
+                ..
+            })
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[package]\n");
+
+        // 空のファイルには書き出せる
+        std::fs::write(&path, "").unwrap();
+        calc.save_session(&path).unwrap();
+        other.load_session(&path).unwrap();
+        assert_eq!(other.stack(), calc.stack());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ng() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        assert_eq!(calc.eval(""), Err(EvalError::EmptyInput));
+        assert_eq!(
+            calc.eval("1 1 1 +"),
+            Err(EvalError::LeftoverValues { count: 2 })
+        );
+        assert_eq!(
+            calc.eval("+ 1 1"),
+            Err(EvalError::StackUnderflow {
+                token: "+".to_string(),
+                span: Span::new(0, 1, 1)
+            })
+        );
+        assert_eq!(
+            calc.eval("1 2 foo"),
+            Err(EvalError::UnknownToken {
+                token: "foo".to_string(),
+                span: Span::new(4, 7, 5)
+            })
+        );
+    }
+}
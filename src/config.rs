@@ -0,0 +1,253 @@
+// 小数点以下の桁数 `precision` の上限。これより大きい値は上限として扱う
+pub const MAX_PRECISION: usize = 1000;
+
+// 固定小数点数の小数点以下の桁数 `scale` の上限。これより大きい設定では計算できない
+pub const MAX_SCALE: u32 = 1000;
+
+/**
+ * RpnCalculator の設定
+ *
+ * 今後のバージョンで設定項目が増えても壊れないように、クレートの外では
+ * `Config::default()` から作ってフィールドを書き換える
+ */
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Config {
+    // トークンごとにスタックの状態を出力する
+    pub verbose: bool,
+    // 演算がオーバーフローした場合の扱い
+    pub arithmetic: Arithmetic,
+    // 浮動小数点数と有理数の小数表記での小数点以下の桁数。None の場合は既定の桁数で出力する
+    pub precision: Option<usize>,
+    // 浮動小数点数の表記
+    pub float_format: FloatFormat,
+    // 有理数の表記
+    pub rational_format: RationalFormat,
+    // 固定小数点数の小数点以下の桁数
+    pub scale: u32,
+    // 固定小数点数の乗除算での丸め方
+    pub rounding: Rounding,
+    // 複素数の表記
+    pub complex_format: ComplexFormat,
+    // 三角関数の角度の単位
+    pub angle: Angle,
+    // ビット演算、基数付きリテラル、10 進以外の出力で使うワード長。四則演算などの結果は切り詰めない
+    pub word_size: WordSize,
+    // ワードを符号付き (2 の補数) として解釈する
+    pub signed: bool,
+    // 整数の出力に使う基数。複数指定した場合は並べて出力する
+    pub radix: Vec<Radix>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            verbose: false,
+            arithmetic: Arithmetic::default(),
+            precision: None,
+            float_format: FloatFormat::default(),
+            rational_format: RationalFormat::default(),
+            scale: 2,
+            rounding: Rounding::default(),
+            complex_format: ComplexFormat::default(),
+            angle: Angle::default(),
+            word_size: WordSize::default(),
+            signed: true,
+            radix: vec![Radix::default()],
+        }
+    }
+}
+
+impl Config {
+    // 出力に使う小数点以下の桁数。`MAX_PRECISION` を超える指定は上限に切り詰める
+    pub(crate) fn clamped_precision(&self) -> Option<usize> {
+        self.precision.map(|p| p.min(MAX_PRECISION))
+    }
+
+    // 固定小数点数の小数点以下の桁数。`MAX_SCALE` を超える場合は None
+    pub(crate) fn checked_scale(&self) -> Option<u32> {
+        (self.scale <= MAX_SCALE).then_some(self.scale)
+    }
+}
+
+/**
+ * オーバーフロー時の演算方法
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arithmetic {
+    // オーバーフローをエラーにする
+    #[default]
+    Checked,
+    // 2 の補数で折り返す
+    Wrapping,
+    // 型の最大値・最小値に丸める
+    Saturating,
+}
+
+/**
+ * 浮動小数点数の表記
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatFormat {
+    // 固定小数点表記 (123.45)
+    Fixed,
+    // 指数表記 (1.2345e2)
+    Sci,
+    // 値の大きさに応じて固定小数点表記と指数表記を切り替える
+    #[default]
+    Auto,
+}
+
+/**
+ * 有理数の表記
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RationalFormat {
+    // 既約分数 (3/2)
+    #[default]
+    Fraction,
+    // 帯分数 (1 1/2)
+    Mixed,
+    // 小数点以下 `precision` 桁の小数 (1.50)
+    Decimal,
+}
+
+/**
+ * 固定小数点数の丸め方
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    // 最近接偶数への丸め (銀行丸め)
+    #[default]
+    HalfEven,
+    // 四捨五入 (0 から遠い方へ)
+    HalfUp,
+    // 0 方向への切り捨て
+    Down,
+    // 0 から遠い方への切り上げ
+    Up,
+    // 正の無限大方向への丸め
+    Ceiling,
+    // 負の無限大方向への丸め
+    Floor,
+}
+
+/**
+ * 複素数の表記
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComplexFormat {
+    // 直交形式 (3+4i)
+    #[default]
+    Rect,
+    // 極形式 (5∠0.927)
+    Polar,
+}
+
+/**
+ * 三角関数の角度の単位
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Angle {
+    // ラジアン
+    #[default]
+    Rad,
+    // 度 (一周 360)
+    Deg,
+    // グラード (一周 400)
+    Grad,
+}
+
+impl Angle {
+    // 一周あたりの大きさ
+    fn full_turn(&self) -> f64 {
+        match self {
+            Angle::Rad => std::f64::consts::TAU,
+            Angle::Deg => 360.0,
+            Angle::Grad => 400.0,
+        }
+    }
+
+    // この単位の角度をラジアンに変換する
+    pub fn radians_of(&self, x: f64) -> f64 {
+        match self {
+            Angle::Rad => x,
+            _ => x / self.full_turn() * std::f64::consts::TAU,
+        }
+    }
+
+    // ラジアンをこの単位の角度に変換する
+    pub fn of_radians(&self, x: f64) -> f64 {
+        match self {
+            Angle::Rad => x,
+            _ => x / std::f64::consts::TAU * self.full_turn(),
+        }
+    }
+}
+
+/**
+ * ビット演算のワード長
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordSize {
+    W8,
+    W16,
+    W32,
+    #[default]
+    W64,
+}
+
+impl WordSize {
+    // ワードのビット数
+    pub fn bits(&self) -> u32 {
+        match self {
+            WordSize::W8 => 8,
+            WordSize::W16 => 16,
+            WordSize::W32 => 32,
+            WordSize::W64 => 64,
+        }
+    }
+
+    // ワードに収まる全てのビットが立った値
+    pub fn mask(&self) -> u128 {
+        (1 << self.bits()) - 1
+    }
+}
+
+/**
+ * 整数の出力に使う基数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Radix {
+    // 10 進数 (255)
+    #[default]
+    Dec,
+    // 16 進数 (0xFF)
+    Hex,
+    // 8 進数 (0o377)
+    Oct,
+    // 2 進数 (0b11111111)
+    Bin,
+}
+
+impl Radix {
+    // 基数
+    pub fn base(&self) -> u32 {
+        match self {
+            Radix::Dec => 10,
+            Radix::Hex => 16,
+            Radix::Oct => 8,
+            Radix::Bin => 2,
+        }
+    }
+
+    // リテラルの接頭辞
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Dec => "",
+            Radix::Hex => "0x",
+            Radix::Oct => "0o",
+            Radix::Bin => "0b",
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::io;
+
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use crate::calculator::RpnCalculator;
+use crate::error::EvalError;
+use crate::number::Number;
+use crate::ops::Arity;
+
+// スタックの表示で省略せずに示す値の個数
+const MAX_STACK_VALUES: usize = 8;
+
+/**
+ * 評価エラーの診断メッセージ
+ *
+ * コンパイラのエラー表示にならって、失敗した行を引用してトークンの下に `^` を付け、
+ * 失敗した時点のスタックと、分かる場合は対処のヒントを添える
+ *
+ * ```text
+ * error: stack underflow: not enough operands for `+` at column 3
+ *  --> calc.txt:4:3
+ *   |
+ * 4 | 1 +
+ *   |   ^
+ *   = stack: 1
+ *   = hint: operator `+` needs 2 operands, stack had 1
+ * ```
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    message: String,
+    // 引用する行と、その中で `^` を付ける文字の範囲
+    snippet: Option<Snippet>,
+    // ファイル名と 1 始まりの行番号
+    location: Option<(String, usize)>,
+    // 底から順に整形したスタックの値
+    stack: Vec<String>,
+    hint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snippet {
+    line: String,
+    // 1 始まりの文字単位の桁と、`^` を付ける文字数
+    column: usize,
+    width: usize,
+}
+
+impl Diagnostic {
+    // 計算機が `source` の評価で返したエラーから診断メッセージを作る
+    pub fn new<N: Number>(calc: &RpnCalculator<N>, error: &EvalError, source: &str) -> Self {
+        let stack = calc.error_stack();
+        Self {
+            message: error.to_string(),
+            snippet: snippet(error, source),
+            location: None,
+            stack: stack.iter().map(|v| v.format(calc.config())).collect(),
+            hint: hint(calc, error.root(), stack.len()),
+        }
+    }
+
+    // ファイル名と 1 始まりの行番号を付ける
+    pub fn with_location(mut self, file: impl Into<String>, line: usize) -> Self {
+        self.location = Some((file.into(), line));
+        self
+    }
+
+    // 対処のヒント
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    // 色指定に対応した出力先に書き出す
+    pub fn emit(&self, out: &mut impl WriteColor) -> io::Result<()> {
+        let error = ColorSpec::new()
+            .set_fg(Some(Color::Red))
+            .set_bold(true)
+            .clone();
+        let gutter = ColorSpec::new()
+            .set_fg(Some(Color::Blue))
+            .set_bold(true)
+            .clone();
+        let bold = ColorSpec::new().set_bold(true).clone();
+
+        out.set_color(&error)?;
+        write!(out, "error")?;
+        out.set_color(&bold)?;
+        writeln!(out, ": {}", self.message)?;
+        out.reset()?;
+
+        // 行番号の桁数に合わせて左端の欄の幅を決める
+        let number = self.location.as_ref().map(|(_, line)| line.to_string());
+        let width = number.as_ref().map_or(1, |n| n.len());
+        let pad = " ".repeat(width);
+
+        if let Some((file, line)) = &self.location {
+            out.set_color(&gutter)?;
+            write!(out, "{}--> ", pad)?;
+            out.reset()?;
+            match &self.snippet {
+                Some(s) => writeln!(out, "{}:{}:{}", file, line, s.column)?,
+                None => writeln!(out, "{}:{}", file, line)?,
+            }
+        }
+
+        if let Some(s) = &self.snippet {
+            out.set_color(&gutter)?;
+            writeln!(out, "{} |", pad)?;
+            write!(out, "{:>width$} | ", number.as_deref().unwrap_or(""))?;
+            out.reset()?;
+            writeln!(out, "{}", s.line)?;
+            out.set_color(&gutter)?;
+            write!(out, "{} | ", pad)?;
+            out.set_color(&error)?;
+            writeln!(out, "{}{}", " ".repeat(s.column - 1), "^".repeat(s.width))?;
+            out.reset()?;
+        }
+
+        self.note(out, &pad, "stack", &self.stack_text())?;
+        if let Some(hint) = &self.hint {
+            self.note(out, &pad, "hint", hint)?;
+        }
+        Ok(())
+    }
+
+    // `= label: text` の形の補足を書く
+    fn note(
+        &self,
+        out: &mut impl WriteColor,
+        pad: &str,
+        label: &str,
+        text: &str,
+    ) -> io::Result<()> {
+        out.set_color(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(true))?;
+        write!(out, "{} = ", pad)?;
+        out.set_color(ColorSpec::new().set_bold(true))?;
+        write!(out, "{}:", label)?;
+        out.reset()?;
+        writeln!(out, " {}", text)
+    }
+
+    // スタックを底から順に並べる。多い場合は底の方を省略する
+    fn stack_text(&self) -> String {
+        match self.stack.len() {
+            0 => "(empty)".to_string(),
+            n if n > MAX_STACK_VALUES => {
+                format!("... {}", self.stack[n - MAX_STACK_VALUES..].join(" "))
+            }
+            _ => self.stack.join(" "),
+        }
+    }
+}
+
+// エラーの位置が `source` の中のトークンを指していれば、その行と範囲を取り出す。
+// 以前の行で作ったクォーテーションの中のエラーは位置が別の行を指すので引用しない
+fn snippet(error: &EvalError, source: &str) -> Option<Snippet> {
+    let (token, span) = (error.token()?, error.span()?);
+    let text = source.get(span.start..span.end)?;
+    if !text.starts_with(token) && !token.starts_with(text) {
+        return None;
+    }
+    let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[span.start..]
+        .find('\n')
+        .map_or(source.len(), |i| span.start + i);
+    // 閉じられていない注釈のように行をまたぐ範囲は行末までにする
+    let end = span.end.min(line_end);
+    Some(Snippet {
+        line: source[line_start..line_end].to_string(),
+        column: span.column,
+        width: source[span.start..end].chars().count().max(1),
+    })
+}
+
+// 失敗したトークンのエラーから対処のヒントを作る
+fn hint<N: Number>(calc: &RpnCalculator<N>, error: &EvalError, depth: usize) -> Option<String> {
+    match error {
+        EvalError::StackUnderflow { token, .. } => {
+            if let Some(name) = token.strip_prefix('=') {
+                return Some(format!(
+                    "`={}` stores the top value, but the stack was empty",
+                    name
+                ));
+            }
+            let (needs, n) = match calc.registry().get(token)?.arity() {
+                Arity::Fixed(n) => ("needs", n),
+                Arity::Variadic(n) => ("needs at least", n),
+            };
+            // 演算子が途中で取る値が足りなかった場合は個数を示せない
+            (depth < n).then(|| {
+                let operands = if n == 1 { "operand" } else { "operands" };
+                format!(
+                    "operator `{}` {} {} {}, stack had {}",
+                    token, needs, n, operands, depth
+                )
+            })
+        }
+        EvalError::UnknownToken { token, .. } => {
+            let registry = calc.registry();
+            let ops = registry.iter().map(|op| op.name().to_string());
+            let aliases = registry.aliases().map(|(alias, _)| alias.to_string());
+            let words = calc.words().map(|(name, _)| name.to_string());
+            let vars = calc.vars().map(|(name, _)| format!("${}", name));
+            let candidate = closest(token, ops.chain(aliases).chain(words).chain(vars))?;
+            Some(format!("did you mean `{}`?", candidate))
+        }
+        EvalError::UndefinedVariable { name, .. } => {
+            match closest(name, calc.vars().map(|(name, _)| name.to_string())) {
+                Some(candidate) => Some(format!("did you mean `{}`?", candidate)),
+                None => Some(format!(
+                    "store a value first, e.g. `1 ={}` or `1 '{} sto`",
+                    name, name
+                )),
+            }
+        }
+        EvalError::UnbalancedBracket { token, .. } if token == "]" => {
+            Some("this `]` has no matching `[`".to_string())
+        }
+        EvalError::UnbalancedBracket { .. } => Some("add `]` to close the quotation".to_string()),
+        EvalError::Unterminated { token, .. } if token == "(" => {
+            Some("comments end with `)`".to_string())
+        }
+        EvalError::Unterminated { .. } => Some("strings end with `\"`".to_string()),
+        EvalError::RedefineBuiltin { token, .. } => {
+            Some(format!("write `:! {} ...` to replace the built-in", token))
+        }
+        _ => None,
+    }
+}
+
+// 編集距離が近い候補のうち最も近いもの。同じ距離なら名前順で先のもの
+fn closest(token: &str, candidates: impl Iterator<Item = String>) -> Option<String> {
+    // 1 文字の名前は候補を挙げず、短い名前は 1 文字違いまで、長い名前は 2 文字違いまでにする
+    let limit = match token.chars().count() {
+        0..=1 => return None,
+        2..=4 => 1,
+        _ => 2,
+    };
+    candidates
+        .map(|c| (edit_distance(token, &c), c))
+        .filter(|(d, _)| *d <= limit)
+        .min()
+        .map(|(_, c)| c)
+}
+
+// 文字単位のレーベンシュタイン距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = prev + usize::from(ca != cb);
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use termcolor::{Ansi, NoColor};
+
+    fn render(diag: &Diagnostic) -> String {
+        let mut out = NoColor::new(Vec::new());
+        diag.emit(&mut out).unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    fn diagnose(calc: &mut RpnCalculator<i32>, source: &str) -> Diagnostic {
+        let e = calc.eval_session(source).unwrap_err();
+        Diagnostic::new(calc, &e, source)
+    }
+
+    #[test]
+    fn test_emit() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        let diag = diagnose(&mut calc, "1 +").with_location("calc.txt", 4);
+        assert_eq!(
+            render(&diag),
+            "error: stack underflow: not enough operands for `+` at column 3\n \
+             --> calc.txt:4:3\n  \
+             |\n\
+             4 | 1 +\n  \
+             |   ^\n  \
+             = stack: 1\n  \
+             = hint: operator `+` needs 2 operands, stack had 1\n"
+        );
+
+        // 行番号がなければ場所を省き、トークンの幅だけ `^` を付ける
+        let diag = diagnose(&mut calc, "@ 3 swapp");
+        assert_eq!(
+            render(&diag),
+            "error: unknown token `@` at column 1\n  \
+             |\n  \
+             | @ 3 swapp\n  \
+             | ^\n  \
+             = stack: (empty)\n"
+        );
+        let diag = diagnose(&mut calc, "3 swapp").with_location("<stdin>", 12);
+        assert!(render(&diag).contains("  |\n12 | 3 swapp\n   |   ^^^^^\n   = stack: 3\n"));
+        assert_eq!(diag.hint(), Some("did you mean `swap`?"));
+
+        // 色を付ける場合はエスケープシーケンスを含む
+        let mut out = Ansi::new(Vec::new());
+        diag.emit(&mut out).unwrap();
+        assert!(String::from_utf8(out.into_inner())
+            .unwrap()
+            .starts_with("\x1b[0m\x1b[1m\x1b[31merror"));
+    }
+
+    #[test]
+    fn test_stack() {
+        // 演算子が失敗した場合は取り出した値もスタックに含める
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        calc.eval_session("5").unwrap();
+        let diag = diagnose(&mut calc, "1 0 /");
+        assert_eq!(diag.stack, ["5", "1", "0"]);
+        assert_eq!(calc.stack(), [crate::value::Value::Num(5)]);
+
+        // ワードの中で失敗した場合は呼び出し位置を示し、ヒントは失敗した演算子について出す
+        calc.eval_session(": add3 + + ;").unwrap();
+        let diag = diagnose(&mut calc, "1 add3");
+        assert_eq!(diag.snippet.as_ref().unwrap().column, 3);
+        assert_eq!(diag.snippet.as_ref().unwrap().width, 4);
+        assert_eq!(
+            diag.hint(),
+            Some("operator `+` needs 2 operands, stack had 1")
+        );
+
+        let diag = diagnose(&mut calc, &format!("{}0 /", "1 ".repeat(10)));
+        assert_eq!(diag.stack_text(), "... 1 1 1 1 1 1 1 0");
+
+        // 1 行ずつ評価する場合も、前の行ではなくその行のスタックを示す
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        let stack = |calc: &mut RpnCalculator<i32>, source: &str| {
+            let e = calc.eval_value(source).unwrap_err();
+            Diagnostic::new(calc, &e, source).stack
+        };
+        assert_eq!(stack(&mut calc, "5 6 7 1 0 /"), ["5", "6", "7", "1", "0"]);
+        assert_eq!(stack(&mut calc, "1 2 3"), ["1", "2", "3"]);
+        assert!(stack(&mut calc, "  ").is_empty());
+    }
+
+    #[test]
+    fn test_hints() {
+        let mut calc = RpnCalculator::<i32>::new(Config::default());
+        calc.eval_session("1 =total").unwrap();
+        assert_eq!(
+            diagnose(&mut calc, "$totl").hint(),
+            Some("did you mean `total`?")
+        );
+        assert_eq!(diagnose(&mut calc, "zzz").hint(), None);
+        assert_eq!(
+            diagnose(&mut calc, "'x rcl").hint(),
+            Some("store a value first, e.g. `1 =x` or `1 'x sto`")
+        );
+        assert_eq!(
+            diagnose(&mut calc, "1 2 ]").hint(),
+            Some("this `]` has no matching `[`")
+        );
+        assert_eq!(
+            diagnose(&mut calc, "clear =y").hint(),
+            Some("`=y` stores the top value, but the stack was empty")
+        );
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "ab"), 2);
+    }
+}
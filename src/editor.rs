@@ -0,0 +1,528 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+// 履歴に残す行数の上限
+const MAX_HISTORY: usize = 1000;
+
+/**
+ * 端末での 1 行入力
+ *
+ * カーソル移動と削除による行の編集、上下キーでの履歴の呼び出し、Tab キーでの補完ができる。
+ * 履歴はファイルに追記して次回の起動時に読み込む
+ */
+pub struct LineEditor {
+    history: Vec<String>,
+    history_file: Option<PathBuf>,
+    completions: Vec<String>,
+}
+
+impl LineEditor {
+    // 履歴ファイルを読み込んでインスタンスを生成する。ファイルがなければ空の履歴で始める
+    pub fn new(history_file: Option<PathBuf>) -> Self {
+        let mut history = history_file
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|s| s.lines().map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        // 上限を超えていれば古い行を捨ててファイルを書き直す
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+            if let Some(path) = &history_file {
+                let _ = fs::write(path, history.join("\n") + "\n");
+            }
+        }
+
+        Self {
+            history,
+            history_file,
+            completions: Vec::new(),
+        }
+    }
+
+    // Tab キーで補完する候補を設定する
+    pub fn set_completions(&mut self, mut completions: Vec<String>) {
+        completions.sort();
+        completions.dedup();
+        self.completions = completions;
+    }
+
+    // プロンプトを表示して標準入力から 1 行読む。入力の終わり (Ctrl-D) では None
+    #[cfg(unix)]
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let _raw = RawMode::enable()?;
+        let line = self.edit(prompt, &mut io::stdin().lock(), &mut io::stdout())?;
+        if let Some(line) = &line {
+            self.add_history(line);
+        }
+        Ok(line)
+    }
+
+    // 端末を 1 文字ずつ読めない環境では、編集せずにプロンプトを出して行単位で読む
+    #[cfg(not(unix))]
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let mut output = io::stdout();
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']).to_string();
+        self.add_history(&line);
+        Ok(Some(line))
+    }
+
+    // 行を履歴に加え、履歴ファイルに追記する
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        if let Some(path) = &self.history_file {
+            // 履歴を保存できなくても入力は続ける
+            if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(f, "{}", line);
+            }
+        }
+    }
+
+    // キー入力を読んで行を編集する
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn edit(
+        &self,
+        prompt: &str,
+        input: &mut impl Read,
+        output: &mut impl Write,
+    ) -> io::Result<Option<String>> {
+        let mut buf = LineBuffer::default();
+        // 履歴を遡っている位置と、遡る前に編集していた行
+        let mut index = self.history.len();
+        let mut draft = String::new();
+        // 直前のキーが Tab だったか
+        let mut tabbed = false;
+
+        buf.redraw(prompt, output)?;
+        loop {
+            let Some(key) = read_key(input)? else {
+                // 入力が途切れた
+                writeln!(output)?;
+                return Ok(None);
+            };
+            let tab = key == Key::Tab;
+            match key {
+                Key::Char(c) => buf.insert(c),
+                Key::Enter => {
+                    write!(output, "\r\n")?;
+                    output.flush()?;
+                    return Ok(Some(buf.text()));
+                }
+                Key::Eof if buf.is_empty() => {
+                    write!(output, "\r\n")?;
+                    output.flush()?;
+                    return Ok(None);
+                }
+                Key::Eof | Key::Delete => buf.delete(),
+                Key::Interrupt => {
+                    // 入力中の行を捨てて新しいプロンプトを出す
+                    write!(output, "^C\r\n")?;
+                    buf = LineBuffer::default();
+                    index = self.history.len();
+                }
+                Key::Backspace => buf.backspace(),
+                Key::Left => buf.left(),
+                Key::Right => buf.right(),
+                Key::Home => buf.cursor = 0,
+                Key::End => buf.cursor = buf.chars.len(),
+                Key::KillStart => buf.kill_start(),
+                Key::KillEnd => buf.kill_end(),
+                Key::Up if index > 0 => {
+                    if index == self.history.len() {
+                        draft = buf.text();
+                    }
+                    index -= 1;
+                    buf = LineBuffer::from(self.history[index].as_str());
+                }
+                Key::Down if index < self.history.len() => {
+                    index += 1;
+                    let line = self.history.get(index).unwrap_or(&draft);
+                    buf = LineBuffer::from(line.as_str());
+                }
+                Key::Tab => {
+                    let candidates = buf.complete(&self.completions);
+                    // 候補が絞り切れないまま Tab を 2 回押したら一覧を出す
+                    if tabbed && candidates.len() > 1 {
+                        write!(output, "\r\n{}\r\n", candidates.join("  "))?;
+                    }
+                }
+                _ => {}
+            }
+            tabbed = tab;
+            buf.redraw(prompt, output)?;
+        }
+    }
+}
+
+/**
+ * 編集中の行とカーソル位置
+ */
+#[derive(Debug, Default, PartialEq)]
+struct LineBuffer {
+    chars: Vec<char>,
+    // 0 始まりの文字位置
+    cursor: usize,
+}
+
+impl From<&str> for LineBuffer {
+    fn from(s: &str) -> Self {
+        let chars = s.chars().collect::<Vec<_>>();
+        let cursor = chars.len();
+        Self { chars, cursor }
+    }
+}
+
+impl LineBuffer {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    fn kill_start(&mut self) {
+        self.chars.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    fn kill_end(&mut self) {
+        self.chars.truncate(self.cursor);
+    }
+
+    // カーソルの前の単語を補完し、前方一致した候補を返す。
+    // 候補が一つなら単語を置き換えて空白を加え、複数なら共通の接頭辞まで伸ばす
+    fn complete<'a>(&mut self, completions: &'a [String]) -> Vec<&'a str> {
+        let start = self.chars[..self.cursor]
+            .iter()
+            .rposition(|c| c.is_whitespace() || *c == '[' || *c == ']')
+            .map_or(0, |i| i + 1);
+        let word = self.chars[start..self.cursor].iter().collect::<String>();
+        if word.is_empty() {
+            return Vec::new();
+        }
+        let candidates = completions
+            .iter()
+            .map(String::as_str)
+            .filter(|c| c.starts_with(&word))
+            .collect::<Vec<_>>();
+
+        let completed = match candidates.as_slice() {
+            [] => return candidates,
+            [only] => format!("{} ", only),
+            [first, rest @ ..] => rest.iter().fold(first.to_string(), |prefix, c| {
+                prefix
+                    .chars()
+                    .zip(c.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            }),
+        };
+        let tail = completed.chars().skip(word.chars().count());
+        for c in tail {
+            self.insert(c);
+        }
+        candidates
+    }
+
+    // プロンプトと行を書き直し、カーソルを移動する
+    fn redraw(&self, prompt: &str, output: &mut impl Write) -> io::Result<()> {
+        write!(output, "\r{}{}\x1b[K", prompt, self.text())?;
+        let back = self.chars.len() - self.cursor;
+        if back > 0 {
+            write!(output, "\x1b[{}D", back)?;
+        }
+        output.flush()
+    }
+}
+
+/**
+ * 行の編集に使うキー
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    // Ctrl-U
+    KillStart,
+    // Ctrl-K
+    KillEnd,
+    // Ctrl-C
+    Interrupt,
+    // Ctrl-D
+    Eof,
+    // 扱わない制御文字とエスケープシーケンス
+    Other,
+}
+
+/**
+ * 入力から 1 キー分のバイト列を読んで解釈する。入力が終わっていれば None
+ */
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(b) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match b {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x05 => Key::End,
+        0x02 => Key::Left,
+        0x06 => Key::Right,
+        0x10 => Key::Up,
+        0x0e => Key::Down,
+        0x15 => Key::KillStart,
+        0x0b => Key::KillEnd,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x1b => read_escape(input)?,
+        b if b < 0x20 => Key::Other,
+        b => {
+            // UTF-8 の先頭バイトから残りのバイト数を求める
+            let len = match b {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut bytes = vec![b];
+            for _ in 1..len {
+                bytes.extend(read_byte(input)?);
+            }
+            match std::str::from_utf8(&bytes) {
+                Ok(s) => Key::Char(s.chars().next().unwrap()),
+                Err(_) => Key::Other,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/**
+ * ESC に続くカーソルキーなどのシーケンスを解釈する
+ */
+fn read_escape(input: &mut impl Read) -> io::Result<Key> {
+    let Some(b'[' | b'O') = read_byte(input)? else {
+        return Ok(Key::Other);
+    };
+    // 数字の引数を読み飛ばして終端の文字で判断する
+    let mut param = Vec::new();
+    loop {
+        match read_byte(input)? {
+            Some(b) if b.is_ascii_digit() || b == b';' => param.push(b),
+            Some(b'~') => {
+                return Ok(match param.as_slice() {
+                    b"1" | b"7" => Key::Home,
+                    b"4" | b"8" => Key::End,
+                    b"3" => Key::Delete,
+                    _ => Key::Other,
+                })
+            }
+            Some(b'A') => return Ok(Key::Up),
+            Some(b'B') => return Ok(Key::Down),
+            Some(b'C') => return Ok(Key::Right),
+            Some(b'D') => return Ok(Key::Left),
+            Some(b'H') => return Ok(Key::Home),
+            Some(b'F') => return Ok(Key::End),
+            _ => return Ok(Key::Other),
+        }
+    }
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut b = [0];
+    match input.read(&mut b)? {
+        0 => Ok(None),
+        _ => Ok(Some(b[0])),
+    }
+}
+
+/**
+ * 端末を 1 文字ずつ読めるモードにし、破棄するときに元に戻す
+ */
+#[cfg(unix)]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: termios は標準入力のファイル記述子に対して取得・設定するだけ
+        unsafe {
+            let mut original = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            // エコー、行単位の入力、シグナルと改行の変換を止める。出力の改行変換は残す
+            raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { original })
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: enable で取得した元の設定に戻すだけ
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(editor: &LineEditor, input: &[u8]) -> Option<String> {
+        let mut output = Vec::new();
+        editor.edit("> ", &mut &input[..], &mut output).unwrap()
+    }
+
+    #[test]
+    fn test_read_key() {
+        let mut input: &[u8] = b"a\x1b[A\x1b[D\x1b[3~\x1bOH\x7f\xe3\x81\x82\r";
+        let mut keys = Vec::new();
+        while let Some(key) = read_key(&mut input).unwrap() {
+            keys.push(key);
+        }
+        assert_eq!(
+            keys,
+            [
+                Key::Char('a'),
+                Key::Up,
+                Key::Left,
+                Key::Delete,
+                Key::Home,
+                Key::Backspace,
+                Key::Char('あ'),
+                Key::Enter
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut editor = LineEditor::new(None);
+        assert_eq!(edit(&editor, b"1 2 +\r").as_deref(), Some("1 2 +"));
+        // 左に移動して挿入、行頭で挿入、Backspace
+        assert_eq!(
+            edit(&editor, b"13\x1b[D2\x01x\x7f\x05 *\r").as_deref(),
+            Some("123 *")
+        );
+        // Ctrl-U と Ctrl-K
+        assert_eq!(
+            edit(&editor, b"abc\x15def\x01\x0b9\r").as_deref(),
+            Some("9")
+        );
+        // 空行での Ctrl-D と入力の終わり
+        assert_eq!(edit(&editor, b"\x04"), None);
+        assert_eq!(edit(&editor, b"12"), None);
+
+        // 履歴
+        editor.add_history("1 2 +");
+        editor.add_history("3 4 *");
+        editor.add_history("3 4 *");
+        assert_eq!(editor.history, ["1 2 +", "3 4 *"]);
+        assert_eq!(edit(&editor, b"\x1b[A\x1b[A\r").as_deref(), Some("1 2 +"));
+        assert_eq!(
+            edit(&editor, b"x\x1b[A\x1b[B\x1b[B\r").as_deref(),
+            Some("x")
+        );
+        assert_eq!(edit(&editor, b"\x1b[A dup\r").as_deref(), Some("3 4 * dup"));
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut editor = LineEditor::new(None);
+        editor.set_completions(
+            ["swap", "sqrt", "sq", "sum", "dup"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        assert_eq!(edit(&editor, b"1 du\t\r").as_deref(), Some("1 dup "));
+        assert_eq!(edit(&editor, b"[sw\t]\r").as_deref(), Some("[swap ]"));
+        assert_eq!(edit(&editor, b"2 sq\t\r").as_deref(), Some("2 sq"));
+        assert_eq!(edit(&editor, b"2 sqr\t\r").as_deref(), Some("2 sqrt "));
+        assert_eq!(edit(&editor, b"xyz\t\r").as_deref(), Some("xyz"));
+
+        let mut buf = LineBuffer::from("1 s");
+        assert_eq!(
+            buf.complete(&editor.completions),
+            ["sq", "sqrt", "sum", "swap"]
+        );
+        assert_eq!(buf.text(), "1 s");
+    }
+
+    #[test]
+    fn test_history_file() {
+        let path = std::env::temp_dir().join(format!("rpncalc-history-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut editor = LineEditor::new(Some(path.clone()));
+        editor.add_history("1 2 +");
+        editor.add_history(" ");
+        editor.add_history("dup *");
+        let editor = LineEditor::new(Some(path.clone()));
+        assert_eq!(editor.history, ["1 2 +", "dup *"]);
+        fs::remove_file(&path).unwrap();
+    }
+}
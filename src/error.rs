@@ -0,0 +1,234 @@
+use thiserror::Error;
+
+use crate::lexer::Span;
+
+/**
+ * 式の評価時に発生するエラー
+ *
+ * `span` はトークンの式の中での位置
+ */
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EvalError {
+    // 演算子に必要な数値がスタックに足りない
+    #[error("stack underflow: not enough operands for `{token}` at {span}")]
+    StackUnderflow { token: String, span: Span },
+    // 数値としても演算子としても解釈できないトークン
+    #[error("unknown token `{token}` at {span}")]
+    UnknownToken { token: String, span: Span },
+    // 閉じられていない文字列リテラルまたは注釈
+    #[error("unterminated `{token}` at {span}")]
+    Unterminated { token: String, span: Span },
+    // 評価後にスタックに複数の値が残っている
+    #[error("invalid syntax: {count} values left on the stack")]
+    LeftoverValues { count: usize },
+    // ゼロ除算
+    #[error("division by zero: `{token}` at {span}")]
+    DivisionByZero { token: String, span: Span },
+    // 演算結果が数値型の範囲を超えた
+    #[error("arithmetic overflow: `{token}` at {span}")]
+    Overflow { token: String, span: Span },
+    // 式にトークンが一つも含まれていない
+    #[error("empty input")]
+    EmptyInput,
+    // 現在の数値の種類では使えない演算子
+    #[error("`{token}` is not supported in this mode at {span}")]
+    Unsupported { token: String, span: Span },
+    // 演算子の引数が定義域の外にある
+    #[error("argument out of domain: `{token}` at {span}")]
+    Domain { token: String, span: Span },
+    // 数値を取る演算子にクォーテーションを渡した、またはその逆
+    #[error("type mismatch: wrong kind of operand for `{token}` at {span}")]
+    TypeMismatch { token: String, span: Span },
+    // 対応する括弧のない `[` または `]`
+    #[error("unbalanced bracket `{token}` at {span}")]
+    UnbalancedBracket { token: String, span: Span },
+    // 評価結果が数値ではない
+    #[error("result is not a number")]
+    NonNumericResult,
+    // `: name ... ;` の形になっていない定義
+    #[error("invalid definition `{token}` at {span}")]
+    BadDefinition { token: String, span: Span },
+    // `:!` を使わずに組み込みの演算子を定義し直そうとした
+    #[error("cannot redefine built-in `{token}` at {span} (use `:!` to force)")]
+    RedefineBuiltin { token: String, span: Span },
+    // ユーザー定義ワードまたはクォーテーションの呼び出しが深くなりすぎた
+    #[error("recursion too deep: `{token}` at {span}")]
+    RecursionLimit { token: String, span: Span },
+    // クォーテーションの `[` の入れ子が深すぎる
+    #[error("quotations nested too deeply: `{token}` at {span}")]
+    NestingLimit { token: String, span: Span },
+    // 値を保存していない変数を参照した
+    #[error("undefined variable `{name}`: `{token}` at {span}")]
+    UndefinedVariable {
+        name: String,
+        token: String,
+        span: Span,
+    },
+    // 取り消す変更、やり直す変更、または直前の値がない
+    #[error("nothing to restore: `{token}` at {span}")]
+    NoHistory { token: String, span: Span },
+    // `save` または `load` でセッションを保存・読み込みできなかった
+    #[error("{source}: `{token}` at {span}")]
+    Session {
+        source: SessionError,
+        token: String,
+        span: Span,
+    },
+    // ユーザー定義ワードの本体で発生したエラー
+    #[error("{source} (in `{word}` called at {span})")]
+    InWord {
+        word: String,
+        span: Span,
+        source: Box<EvalError>,
+    },
+}
+
+impl EvalError {
+    // エラーの起きたトークン。ワードの中のエラーでは呼び出したワードの名前
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            EvalError::LeftoverValues { .. }
+            | EvalError::EmptyInput
+            | EvalError::NonNumericResult => None,
+            EvalError::InWord { word, .. } => Some(word),
+            EvalError::UndefinedVariable { token, .. }
+            | EvalError::Session { token, .. }
+            | EvalError::StackUnderflow { token, .. }
+            | EvalError::UnknownToken { token, .. }
+            | EvalError::Unterminated { token, .. }
+            | EvalError::DivisionByZero { token, .. }
+            | EvalError::Overflow { token, .. }
+            | EvalError::Unsupported { token, .. }
+            | EvalError::Domain { token, .. }
+            | EvalError::TypeMismatch { token, .. }
+            | EvalError::UnbalancedBracket { token, .. }
+            | EvalError::BadDefinition { token, .. }
+            | EvalError::RedefineBuiltin { token, .. }
+            | EvalError::RecursionLimit { token, .. }
+            | EvalError::NestingLimit { token, .. }
+            | EvalError::NoHistory { token, .. } => Some(token),
+        }
+    }
+
+    // `token` の式の中での位置
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EvalError::LeftoverValues { .. }
+            | EvalError::EmptyInput
+            | EvalError::NonNumericResult => None,
+            EvalError::InWord { span, .. }
+            | EvalError::UndefinedVariable { span, .. }
+            | EvalError::Session { span, .. }
+            | EvalError::StackUnderflow { span, .. }
+            | EvalError::UnknownToken { span, .. }
+            | EvalError::Unterminated { span, .. }
+            | EvalError::DivisionByZero { span, .. }
+            | EvalError::Overflow { span, .. }
+            | EvalError::Unsupported { span, .. }
+            | EvalError::Domain { span, .. }
+            | EvalError::TypeMismatch { span, .. }
+            | EvalError::UnbalancedBracket { span, .. }
+            | EvalError::BadDefinition { span, .. }
+            | EvalError::RedefineBuiltin { span, .. }
+            | EvalError::RecursionLimit { span, .. }
+            | EvalError::NestingLimit { span, .. }
+            | EvalError::NoHistory { span, .. } => Some(*span),
+        }
+    }
+
+    // ワードの呼び出しをたどった、実際に失敗したトークンのエラー
+    pub fn root(&self) -> &EvalError {
+        match self {
+            EvalError::InWord { source, .. } => source.root(),
+            e => e,
+        }
+    }
+}
+
+/**
+ * 数値演算のエラー
+ *
+ * 評価器がトークンと位置を付けて `EvalError` に変換する
+ */
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArithError {
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("not supported in this mode")]
+    Unsupported,
+    #[error("argument out of domain")]
+    Domain,
+    #[error("not enough values on the stack")]
+    StackUnderflow,
+    #[error("wrong kind of operand")]
+    Type,
+    #[error("undefined variable `{0}`")]
+    UndefinedVariable(String),
+    #[error("nothing to restore")]
+    NoHistory,
+    #[error("recursion too deep")]
+    RecursionLimit,
+    #[error("quotations nested too deeply")]
+    NestingLimit,
+    #[error("{0}")]
+    Session(SessionError),
+    // クォーテーションの評価中に発生したエラー。位置はクォーテーション内のトークンを指す
+    #[error("{0}")]
+    Nested(Box<EvalError>),
+}
+
+impl ArithError {
+    // トークンと位置を付けて評価エラーに変換する
+    pub(crate) fn at(self, token: &str, span: Span) -> EvalError {
+        let token = token.to_string();
+        match self {
+            ArithError::DivisionByZero => EvalError::DivisionByZero { token, span },
+            ArithError::Overflow => EvalError::Overflow { token, span },
+            ArithError::Unsupported => EvalError::Unsupported { token, span },
+            ArithError::Domain => EvalError::Domain { token, span },
+            ArithError::StackUnderflow => EvalError::StackUnderflow { token, span },
+            ArithError::Type => EvalError::TypeMismatch { token, span },
+            ArithError::UndefinedVariable(name) => {
+                EvalError::UndefinedVariable { name, token, span }
+            }
+            ArithError::NoHistory => EvalError::NoHistory { token, span },
+            ArithError::RecursionLimit => EvalError::RecursionLimit { token, span },
+            ArithError::NestingLimit => EvalError::NestingLimit { token, span },
+            ArithError::Session(source) => EvalError::Session {
+                source,
+                token,
+                span,
+            },
+            ArithError::Nested(e) => *e,
+        }
+    }
+}
+
+/**
+ * セッションの保存と読み込みのエラー
+ *
+ * `line` は 1 始まりのファイルの行番号
+ */
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionError {
+    // ファイルを読み書きできない
+    #[error("cannot access session file `{path}`: {message}")]
+    Io { path: String, message: String },
+    // 先頭にセッションファイルの見出しがない
+    #[error("not a session file")]
+    NotSession,
+    // このバージョンでは読めない形式
+    #[error("unsupported session version {found} (expected {expected})")]
+    Version { found: u32, expected: u32 },
+    // 計算機と異なる種類の数値で保存されている
+    #[error("session was saved with `{found}` numbers, not `{expected}`")]
+    NumberKind { found: String, expected: String },
+    // 解釈できない行
+    #[error("corrupt session at line {line}: {reason}")]
+    Corrupt { line: usize, reason: String },
+}
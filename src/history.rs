@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+
+use crate::value::Value;
+
+// 取り消せる行数の上限
+const MAX_UNDO: usize = 500;
+
+/**
+ * セッションのスタックの変更履歴
+ *
+ * スタック全体を複製せず、行の前後で変わらなかった底の部分を共有して、
+ * 変わった部分だけを差分として持つ
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct History<N> {
+    undo: VecDeque<Diff<N>>,
+    redo: Vec<Diff<N>>,
+}
+
+/**
+ * 別の時点のスタックに戻すための差分
+ *
+ * 戻し先のスタックは、現在のスタックの底から `keep` 個の値に `tail` を積んだもの
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct Diff<N> {
+    keep: usize,
+    tail: Vec<Value<N>>,
+}
+
+impl<N: Clone + PartialEq> Diff<N> {
+    // `to` から `from` に戻すための差分
+    fn between(from: &[Value<N>], to: &[Value<N>]) -> Self {
+        let keep = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+        Self {
+            keep,
+            tail: from[keep..].to_vec(),
+        }
+    }
+
+    // 差分を当てたスタックと、当てる前に戻すための差分を返す
+    fn apply(self, stack: &[Value<N>]) -> (Vec<Value<N>>, Self) {
+        let inverse = Self {
+            keep: self.keep,
+            tail: stack[self.keep..].to_vec(),
+        };
+        let mut res = stack[..self.keep].to_vec();
+        res.extend(self.tail);
+        (res, inverse)
+    }
+}
+
+impl<N: Clone + PartialEq> History<N> {
+    // 行の評価でスタックが `before` から `after` に変わったことを記録する。
+    // 変化がなければ何もしない。新しい変更を記録するとやり直しの履歴は捨てる
+    pub(crate) fn record(&mut self, before: &[Value<N>], after: &[Value<N>]) {
+        if before == after {
+            return;
+        }
+        self.undo.push_back(Diff::between(before, after));
+        if self.undo.len() > MAX_UNDO {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    // 直前の変更を取り消したスタックを返す。履歴がなければ None
+    pub(crate) fn undo(&mut self, stack: &[Value<N>]) -> Option<Vec<Value<N>>> {
+        let (res, inverse) = self.undo.pop_back()?.apply(stack);
+        self.redo.push(inverse);
+        Some(res)
+    }
+
+    // 取り消した変更をやり直したスタックを返す。履歴がなければ None
+    pub(crate) fn redo(&mut self, stack: &[Value<N>]) -> Option<Vec<Value<N>>> {
+        let (res, inverse) = self.redo.pop()?.apply(stack);
+        self.undo.push_back(inverse);
+        Some(res)
+    }
+}
+
+impl<N> Default for History<N> {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nums(ns: &[i32]) -> Vec<Value<i32>> {
+        ns.iter().copied().map(Value::Num).collect()
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        history.record(&nums(&[]), &nums(&[1, 2]));
+        history.record(&nums(&[1, 2]), &nums(&[3]));
+        history.record(&nums(&[3]), &nums(&[3]));
+        assert_eq!(history.undo.len(), 2);
+
+        let stack = history.undo(&nums(&[3])).unwrap();
+        assert_eq!(stack, nums(&[1, 2]));
+        let stack = history.undo(&stack).unwrap();
+        assert_eq!(stack, nums(&[]));
+        assert_eq!(history.undo(&stack), None);
+
+        let stack = history.redo(&stack).unwrap();
+        assert_eq!(stack, nums(&[1, 2]));
+
+        // 新しい変更を記録するとやり直せなくなる
+        history.record(&stack, &nums(&[1, 2, 4]));
+        assert_eq!(history.redo(&nums(&[1, 2, 4])), None);
+        assert_eq!(history.undo(&nums(&[1, 2, 4])).unwrap(), nums(&[1, 2]));
+    }
+
+    #[test]
+    fn test_diff() {
+        // 変わらなかった底の部分は差分に含めない
+        let before = nums(&(0..1000).collect::<Vec<_>>());
+        let mut after = before.clone();
+        after.pop();
+        after.push(Value::Num(-1));
+        let diff = Diff::between(&before, &after);
+        assert_eq!(diff.keep, 999);
+        assert_eq!(diff.tail, nums(&[999]));
+
+        let mut history = History::default();
+        for _ in 0..MAX_UNDO + 10 {
+            history.record(&before, &after);
+        }
+        assert_eq!(history.undo.len(), MAX_UNDO);
+    }
+}
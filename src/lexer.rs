@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::error::EvalError;
+
+/**
+ * トークンの式の中での位置
+ *
+ * `start` と `end` はバイト位置、`column` は行頭からの 1 始まりの文字数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, column: usize) -> Self {
+        Self { start, end, column }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}", self.column)
+    }
+}
+
+/**
+ * トークンの種類
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    // 数値、演算子、ワード、括弧など、書いたとおりの文字列
+    Word,
+    // `"..."` の文字列リテラル。エスケープを解いた中身を持つ
+    Str(String),
+    // `'name` または `'"..."` の変数名
+    Symbol(String),
+}
+
+/**
+ * 字句解析したトークン
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind,
+    // 式に書かれていたとおりの文字列
+    pub(crate) text: &'a str,
+    pub(crate) span: Span,
+}
+
+/**
+ * 式をトークンに分ける
+ *
+ * 空白で区切り、`[` と `]` は続けて書いても独立したトークンにする。
+ * `#` から行末までと、`(` から `)` までは注釈として読み飛ばす。
+ * `(` は後に空白が続く場合だけ注釈の始まりとみなす
+ */
+pub(crate) fn tokenize(source: &str) -> Result<Vec<Token<'_>>, EvalError> {
+    let mut lexer = Lexer { source, pos: 0 };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/**
+ * 文字列を `"..."` の形に引用する
+ */
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/**
+ * `'name` の形で書ける変数名かどうか
+ */
+pub(crate) fn is_bare_symbol(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('"') && !name.contains(is_delimiter)
+}
+
+// 単語を区切る文字
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || c == '[' || c == ']'
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    // 次に読むバイト位置
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    // 注釈と空白を読み飛ばして次のトークンを読む
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, EvalError> {
+        loop {
+            let rest = &self.source[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Ok(None);
+            };
+            if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else if c == '#' {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest == "("
+                || (rest.starts_with('(') && rest[1..].starts_with(char::is_whitespace))
+            {
+                let end = rest
+                    .find(')')
+                    .ok_or_else(|| self.unterminated("(", rest.len()))?;
+                self.pos += end + 1;
+            } else {
+                return self.read_token().map(Some);
+            }
+        }
+    }
+
+    // 空白でない位置から 1 トークン読む
+    fn read_token(&mut self) -> Result<Token<'a>, EvalError> {
+        let start = self.pos;
+        let rest = &self.source[start..];
+        let (kind, len) = if rest.starts_with(['[', ']']) {
+            (TokenKind::Word, 1)
+        } else if rest.starts_with('"') {
+            let (s, len) = self.read_string(rest)?;
+            (TokenKind::Str(s), len)
+        } else if rest.starts_with("'\"") {
+            let (s, len) = self.read_string(&rest[1..])?;
+            (TokenKind::Symbol(s), len + 1)
+        } else {
+            let len = rest.find(is_delimiter).unwrap_or(rest.len());
+            match rest[..len].strip_prefix('\'') {
+                Some(name) if !name.is_empty() => (TokenKind::Symbol(name.to_string()), len),
+                _ => (TokenKind::Word, len),
+            }
+        };
+        self.pos += len;
+        Ok(Token {
+            kind,
+            text: &self.source[start..start + len],
+            span: self.span(start, start + len),
+        })
+    }
+
+    // `"` で始まる文字列リテラルを読み、中身と閉じる `"` までのバイト数を返す
+    fn read_string(&self, rest: &str) -> Result<(String, usize), EvalError> {
+        let mut s = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((s, i + 1)),
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, c)) => s.push(c),
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+        Err(self.unterminated("\"", self.source.len() - self.pos))
+    }
+
+    // 閉じられていない注釈または文字列リテラルのエラー
+    fn unterminated(&self, token: &str, len: usize) -> EvalError {
+        EvalError::Unterminated {
+            token: token.to_string(),
+            span: self.span(self.pos, self.pos + len),
+        }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let column = self.source[line_start..start].chars().count() + 1;
+        Span::new(start, end, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<&str> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(texts("1 2 +"), ["1", "2", "+"]);
+        assert_eq!(texts(" [1 [2]]dup "), ["[", "1", "[", "2", "]", "]", "dup"]);
+        assert_eq!(texts(""), Vec::<&str>::new());
+
+        // 位置はバイトと文字の両方で数える
+        let tokens = tokenize("π 2 *").unwrap();
+        assert_eq!(tokens[1].span, Span::new(3, 4, 3));
+        assert_eq!(tokens[2].span, Span::new(5, 6, 5));
+        let tokens = tokenize("1\n  22").unwrap();
+        assert_eq!(tokens[1].span, Span::new(4, 6, 3));
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(texts("1 2 + # 合計"), ["1", "2", "+"]);
+        assert_eq!(texts("#\n3"), ["3"]);
+        assert_eq!(
+            texts(": sq ( x -- x*x ) dup * ;"),
+            [":", "sq", "dup", "*", ";"]
+        );
+        assert_eq!(texts("1 (2) a#b"), ["1", "(2)", "a#b"]);
+        assert_eq!(
+            tokenize("1 ( x -- "),
+            Err(EvalError::Unterminated {
+                token: "(".to_string(),
+                span: Span::new(2, 9, 3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        let tokens = tokenize(r#""a b" 'x '"c \"d\"" ' "\n""#).unwrap();
+        let kinds = tokens.iter().map(|t| t.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Str("a b".to_string()),
+                TokenKind::Symbol("x".to_string()),
+                TokenKind::Symbol("c \"d\"".to_string()),
+                TokenKind::Word,
+                TokenKind::Str("\n".to_string()),
+            ]
+        );
+        assert_eq!(tokens[2].text, r#"'"c \"d\"""#);
+        assert_eq!(tokens[2].span, Span::new(9, 19, 10));
+        assert_eq!(
+            tokenize("1 \"abc"),
+            Err(EvalError::Unterminated {
+                token: "\"".to_string(),
+                span: Span::new(2, 6, 3)
+            })
+        );
+
+        assert_eq!(quote("c \"d\"\n"), r#""c \"d\"\n""#);
+        assert!(is_bare_symbol("x1"));
+        assert!(!is_bare_symbol("a b"));
+        assert!(!is_bare_symbol("\"x"));
+    }
+}
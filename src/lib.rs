@@ -0,0 +1,33 @@
+/*!
+ * 逆ポーランド記法 (RPN) の計算機ライブラリ
+ *
+ * ```
+ * use rpncalc::{Config, RpnCalculator};
+ *
+ * let mut calc = RpnCalculator::<i32>::new(Config::default());
+ * assert_eq!(calc.eval("1 2 + 3 *").unwrap(), 9);
+ * ```
+ */
+
+mod calculator;
+mod config;
+mod diagnostic;
+mod error;
+mod history;
+mod lexer;
+mod number;
+mod ops;
+mod session;
+mod value;
+
+pub use calculator::{Context, RpnCalculator};
+pub use config::{
+    Angle, Arithmetic, ComplexFormat, Config, FloatFormat, Radix, RationalFormat, Rounding,
+    WordSize, MAX_PRECISION, MAX_SCALE,
+};
+pub use diagnostic::Diagnostic;
+pub use error::{ArithError, EvalError, SessionError};
+pub use lexer::Span;
+pub use number::{BigInt, Complex, Decimal, Function, Number, Rational};
+pub use ops::{Arity, Op, Operator, OperatorRegistry, ValueOp};
+pub use value::{Quotation, Term, Value};
@@ -1,9 +1,33 @@
-use anyhow::{bail, ensure, Context, Result};
+mod editor;
 
-use clap::Parser;
+use anyhow::{Context, Result};
+
+use clap::{ArgEnum, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueSource};
+use editor::LineEditor;
+use rpncalc::{
+    Angle, Arithmetic, BigInt, Complex, ComplexFormat, Config, Decimal, Diagnostic, EvalError,
+    FloatFormat, Number, Radix, Rational, RationalFormat, Rounding, RpnCalculator, WordSize,
+    MAX_PRECISION, MAX_SCALE,
+};
+use std::env;
+use std::fmt;
 use std::fs::File;
-use std::io::{stdin, BufRead, BufReader};
-use std::path::PathBuf;
+use std::io::{stderr, stdin, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use termcolor::{ColorChoice, StandardStream};
+
+// 対話モードのプロンプト
+const PROMPT: &str = "rpn> ";
+
+// 対話モードの履歴ファイルの既定の名前。ホームディレクトリに置く
+const HISTORY_FILE: &str = ".rpncalc_history";
+
+// 評価に失敗した行があった場合の終了ステータス
+const EXIT_FAILED: u8 = 1;
+
+// ファイルを開けないなど、入力を処理できなかった場合の終了ステータス
+const EXIT_ERROR: u8 = 2;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -17,141 +41,691 @@ struct Opts {
     #[clap(short, long)]
     verbose: bool,
 
+    // Wraps around on overflow instead of reporting an error
+    #[clap(long, conflicts_with = "saturating")]
+    wrapping: bool,
+
+    // Clamps to the numeric bounds on overflow instead of reporting an error
+    #[clap(long)]
+    saturating: bool,
+
+    // Kind of numbers used for evaluation
+    #[clap(long, arg_enum, default_value = "int")]
+    mode: Mode,
+
+    // Integer type used in int mode
+    #[clap(long = "int", arg_enum, default_value = "i32")]
+    int_kind: IntKind,
+
+    // Number of digits after the decimal point in float mode and rational decimal output
+    #[clap(long, validator = parse_precision)]
+    precision: Option<usize>,
+
+    // Notation of results in float mode
+    #[clap(long = "format", arg_enum, default_value = "auto")]
+    float_format: FormatArg,
+
+    // Notation of results in rational mode
+    #[clap(long, arg_enum, default_value = "fraction")]
+    rational_format: RationalFormatArg,
+
+    // Number of digits after the decimal point in decimal mode
+    #[clap(long, default_value_t = 2, validator = parse_scale)]
+    scale: u32,
+
+    // Rounding applied by multiplication and division in decimal mode
+    #[clap(long, arg_enum, default_value = "half-even")]
+    rounding: RoundingArg,
+
+    // Notation of results in complex mode
+    #[clap(long, arg_enum, default_value = "rect")]
+    complex_format: ComplexFormatArg,
+
+    // Unit of angles taken and returned by trigonometric functions
+    #[clap(long, arg_enum, default_value = "rad")]
+    angle: AngleArg,
+
+    // Bases used to print integer results; several may be given separated by commas
+    #[clap(long, arg_enum, use_value_delimiter = true, default_value = "dec")]
+    radix: Vec<RadixArg>,
+
+    // Word size in bits for bitwise operators, radix literals and radix output;
+    // ordinary arithmetic such as + and * is not truncated to it
+    #[clap(long, arg_enum, default_value = "64")]
+    word_size: WordSizeArg,
+
+    // Interpret words as unsigned instead of two's complement
+    #[clap(long)]
+    unsigned: bool,
+
+    // Variable set before the formulas are evaluated; the value may be a formula
+    #[clap(long = "var", value_name = "NAME=VALUE")]
+    vars: Vec<String>,
+
+    // Keep the stack across lines and print the remaining values at the end of input
+    #[clap(long)]
+    session: bool,
+
+    // History file of the interactive mode [default: ~/.rpncalc_history]
+    #[clap(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+
+    // File of word definitions evaluated before the formulas
+    #[clap(long, value_name = "FILE")]
+    prelude: Option<PathBuf>,
+
+    // Restore the stack, variables, words and settings saved by --save-session
+    #[clap(long, value_name = "PATH")]
+    load_session: Option<PathBuf>,
+
+    // Save the stack, variables, words and settings when the input ends
+    #[clap(long, value_name = "PATH")]
+    save_session: Option<PathBuf>,
+
+    // Stop at the first line that fails instead of reporting every error
+    #[clap(long)]
+    fail_fast: bool,
+
+    // When to color error messages
+    #[clap(long, arg_enum, default_value = "auto")]
+    color: ColorArg,
+
     // Formulas written in RPN
     #[clap(name = "FILE")]
     formula_file: Option<PathBuf>,
+
+    // コマンドラインで明示した計算機の設定の引数
+    #[clap(skip)]
+    explicit: Vec<&'static str>,
 }
 
+// 計算機の設定を決める引数。`--load-session` で読み込んだ設定より優先する
+const SETTING_ARGS: [&str; 12] = [
+    "wrapping",
+    "saturating",
+    "precision",
+    "float-format",
+    "rational-format",
+    "scale",
+    "rounding",
+    "complex-format",
+    "angle",
+    "radix",
+    "word-size",
+    "unsigned",
+];
+
 /**
- * RpnCalculator
- */
-struct RpnCalculator(bool);
-
-impl RpnCalculator {
-    // コンストラクタ?自身の構造体のパラメータを初期化
-    pub fn new(verbose: bool) -> Self {
-        Self(verbose)
-    }
-
-    // 行をパースして計算を実行する
-    pub fn eval(&self, formula: &str) -> Result<i32> {
-        // 文字列を空白でパースしトークンのVecを取得
-        let mut tokens = formula.split_whitespace().rev().collect::<Vec<_>>();
-
-        // 計算を実行して返す
-        self.eval_inner(&mut tokens)
-    }
-
-    // 計算処理
-    fn eval_inner(&self, tokens: &mut Vec<&str>) -> Result<i32> {
-        // スタックの生成
-        let mut stack = Vec::new();
-        let mut pos = 0;
-
-        // トークンが取り出せなくなるまでループ
-        while let Some(token) = tokens.pop() {
-            pos += 1;
-            // トークンが数値だった場合
-            if let Ok(x) = token.parse::<i32>() {
-                // スタックに保存
-                stack.push(x);
-            } else {
-                // トークンが数値以外の場合は、スタックから数値を取り出す
-                let y = stack.pop().context(format!("invalid syntax at {}", pos))?;
-                let x = stack.pop().context(format!("invalid syntax at {}", pos))?;
-                // 取り出した数値をトークンの種類に応じて計算
-                let res = match token {
-                    "+" => x + y,
-                    "-" => x - y,
-                    "*" => x * y,
-                    "/" => x / y,
-                    "%" => x % y,
-                    _ => bail!("invalid token at {}", pos),
-                };
-                // 計算結果をスタックに保存
-                stack.push(res);
-            }
+ * `--precision` の値を読み、上限を超えていればエラーにする
+ */
+fn parse_precision(s: &str) -> Result<usize, String> {
+    let precision: usize = s.parse().map_err(|e| format!("{}", e))?;
+    if precision > MAX_PRECISION {
+        return Err(format!("must be at most {}", MAX_PRECISION));
+    }
+    Ok(precision)
+}
 
-            // `-v` オプションが指定されている場合は、この時点でのトークンとスタックの状態を出力
-            if self.0 {
-                println!("{:?} {:?}", tokens, stack);
-            }
+/**
+ * `--scale` の値を読み、上限を超えていればエラーにする
+ */
+fn parse_scale(s: &str) -> Result<u32, String> {
+    let scale: u32 = s.parse().map_err(|e| format!("{}", e))?;
+    if scale > MAX_SCALE {
+        return Err(format!("must be at most {}", MAX_SCALE));
+    }
+    Ok(scale)
+}
+
+/**
+ * 計算に使う数値の種類
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum Mode {
+    Int,
+    Float,
+    Rational,
+    Decimal,
+    Complex,
+}
+
+/**
+ * 計算に使う整数型
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum IntKind {
+    I32,
+    I64,
+    I128,
+    Big,
+}
+
+/**
+ * 浮動小数点数の表記
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum FormatArg {
+    Fixed,
+    Sci,
+    Auto,
+}
+
+impl From<FormatArg> for FloatFormat {
+    fn from(arg: FormatArg) -> Self {
+        match arg {
+            FormatArg::Fixed => FloatFormat::Fixed,
+            FormatArg::Sci => FloatFormat::Sci,
+            FormatArg::Auto => FloatFormat::Auto,
+        }
+    }
+}
+
+/**
+ * 有理数の表記
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum RationalFormatArg {
+    Fraction,
+    Mixed,
+    Decimal,
+}
+
+impl From<RationalFormatArg> for RationalFormat {
+    fn from(arg: RationalFormatArg) -> Self {
+        match arg {
+            RationalFormatArg::Fraction => RationalFormat::Fraction,
+            RationalFormatArg::Mixed => RationalFormat::Mixed,
+            RationalFormatArg::Decimal => RationalFormat::Decimal,
+        }
+    }
+}
+
+/**
+ * 固定小数点数の丸め方
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum RoundingArg {
+    HalfEven,
+    HalfUp,
+    Down,
+    Up,
+    Ceiling,
+    Floor,
+}
+
+impl From<RoundingArg> for Rounding {
+    fn from(arg: RoundingArg) -> Self {
+        match arg {
+            RoundingArg::HalfEven => Rounding::HalfEven,
+            RoundingArg::HalfUp => Rounding::HalfUp,
+            RoundingArg::Down => Rounding::Down,
+            RoundingArg::Up => Rounding::Up,
+            RoundingArg::Ceiling => Rounding::Ceiling,
+            RoundingArg::Floor => Rounding::Floor,
+        }
+    }
+}
+
+/**
+ * 複素数の表記
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum ComplexFormatArg {
+    Rect,
+    Polar,
+}
+
+impl From<ComplexFormatArg> for ComplexFormat {
+    fn from(arg: ComplexFormatArg) -> Self {
+        match arg {
+            ComplexFormatArg::Rect => ComplexFormat::Rect,
+            ComplexFormatArg::Polar => ComplexFormat::Polar,
         }
+    }
+}
 
-        // スタックにデータが複数残っている場合はエラー
-        ensure!(stack.len() == 1, "invalid syntax");
+/**
+ * 角度の単位
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum AngleArg {
+    Rad,
+    Deg,
+    Grad,
+}
 
-        Ok(stack[0])
+impl From<AngleArg> for Angle {
+    fn from(arg: AngleArg) -> Self {
+        match arg {
+            AngleArg::Rad => Angle::Rad,
+            AngleArg::Deg => Angle::Deg,
+            AngleArg::Grad => Angle::Grad,
+        }
+    }
+}
+
+/**
+ * 整数の出力に使う基数
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum RadixArg {
+    Dec,
+    Hex,
+    Oct,
+    Bin,
+}
+
+impl From<RadixArg> for Radix {
+    fn from(arg: RadixArg) -> Self {
+        match arg {
+            RadixArg::Dec => Radix::Dec,
+            RadixArg::Hex => Radix::Hex,
+            RadixArg::Oct => Radix::Oct,
+            RadixArg::Bin => Radix::Bin,
+        }
+    }
+}
+
+/**
+ * ビット演算のワード長
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum WordSizeArg {
+    #[clap(name = "8")]
+    W8,
+    #[clap(name = "16")]
+    W16,
+    #[clap(name = "32")]
+    W32,
+    #[clap(name = "64")]
+    W64,
+}
+
+impl From<WordSizeArg> for WordSize {
+    fn from(arg: WordSizeArg) -> Self {
+        match arg {
+            WordSizeArg::W8 => WordSize::W8,
+            WordSizeArg::W16 => WordSize::W16,
+            WordSizeArg::W32 => WordSize::W32,
+            WordSizeArg::W64 => WordSize::W64,
+        }
+    }
+}
+
+/**
+ * エラーメッセージに色を付けるかどうか
+ */
+#[derive(ArgEnum, Clone, Copy, Debug)]
+enum ColorArg {
+    // 標準エラー出力が端末の場合だけ色を付ける
+    Auto,
+    Always,
+    Never,
+}
+
+impl Opts {
+    // コマンドライン引数から計算機の設定を組み立てる
+    fn config(&self) -> Config {
+        // オーバーフロー時の演算方法
+        let arithmetic = if self.wrapping {
+            Arithmetic::Wrapping
+        } else if self.saturating {
+            Arithmetic::Saturating
+        } else {
+            Arithmetic::Checked
+        };
+
+        let mut config = Config::default();
+        config.verbose = self.verbose;
+        config.arithmetic = arithmetic;
+        config.precision = self.precision;
+        config.float_format = self.float_format.into();
+        config.rational_format = self.rational_format.into();
+        config.scale = self.scale;
+        config.rounding = self.rounding.into();
+        config.complex_format = self.complex_format.into();
+        config.angle = self.angle.into();
+        config.word_size = self.word_size.into();
+        config.signed = !self.unsigned;
+        config.radix = self.radix.iter().map(|&r| r.into()).collect();
+        config
+    }
+
+    // コマンドラインで明示した設定の引数を記録する
+    fn record_explicit(&mut self, matches: &ArgMatches) {
+        self.explicit = SETTING_ARGS
+            .into_iter()
+            .filter(|&id| matches.value_source(id) == Some(ValueSource::CommandLine))
+            .collect();
+    }
+
+    // コマンドラインで明示した設定で `config` を上書きする
+    fn apply_explicit(&self, config: &mut Config) {
+        let given = self.config();
+        for &id in &self.explicit {
+            match id {
+                "wrapping" | "saturating" => config.arithmetic = given.arithmetic,
+                "precision" => config.precision = given.precision,
+                "float-format" => config.float_format = given.float_format,
+                "rational-format" => config.rational_format = given.rational_format,
+                "scale" => config.scale = given.scale,
+                "rounding" => config.rounding = given.rounding,
+                "complex-format" => config.complex_format = given.complex_format,
+                "angle" => config.angle = given.angle,
+                "radix" => config.radix = given.radix.clone(),
+                "word-size" => config.word_size = given.word_size,
+                "unsigned" => config.signed = given.signed,
+                _ => unreachable!("unknown setting argument {}", id),
+            }
+        }
+    }
+
+    // エラーメッセージの色付けの指定。auto では端末でなければ色を付けない
+    fn color_choice(&self) -> ColorChoice {
+        match self.color {
+            ColorArg::Auto if stderr().is_terminal() => ColorChoice::Auto,
+            ColorArg::Auto | ColorArg::Never => ColorChoice::Never,
+            ColorArg::Always => ColorChoice::Always,
+        }
     }
 }
 
 /**
  * メイン処理
+ *
+ * 評価に失敗した行があれば 1、入力を処理できなければ 2 で終了する
+ */
+fn main() -> ExitCode {
+    match try_main() {
+        Ok(summary) if summary.failed > 0 => ExitCode::from(EXIT_FAILED),
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/**
+ * 入力元を選んで計算を実行し、行の評価結果の集計を返す
  */
-fn main() -> Result<()> {
+fn try_main() -> Result<Summary> {
     // Clapで提供された構造体を使ってコマンドライン引数を取得
-    let opts = Opts::parse();
+    let matches = Opts::command().get_matches();
+    let mut opts = Opts::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    opts.record_explicit(&matches);
+    let config = opts.config();
 
     // コマンドに渡されたのがファイルだった場合
-    if let Some(path) = opts.formula_file {
+    if let Some(path) = &opts.formula_file {
         // ファイルをオープンしハンドラを取得
-        let f = File::open(path)?;
+        let f = File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
         // ハンドラからリーダーを取得
         let reader = BufReader::new(f);
-        run(reader, opts.verbose)
+        dispatch(Input::Lines(reader), &opts, config)
+    } else if stdin().is_terminal() {
+        // 端末から起動された場合は対話モード
+        dispatch(Input::<BufReader<File>>::Terminal, &opts, config)
     } else {
         // コマンドに標準入力が渡された場合
         let stdin = stdin();
         // 標準入力からリーダーを取得
         let reader = stdin.lock();
-        run(reader, opts.verbose)
+        dispatch(Input::Lines(reader), &opts, config)
     }
 }
 
 /**
- * リーダーで行を取得し計算を実行する処理
+ * 式の入力元
+ */
+enum Input<R> {
+    // ファイルまたはパイプから行を読む
+    Lines(R),
+    // 端末で対話的に入力する
+    Terminal,
+}
+
+/**
+ * 入力の行の評価結果の集計
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Summary {
+    // 評価した行の数。空白だけの行は数えない
+    processed: usize,
+    // 評価に失敗した行の数
+    failed: usize,
+}
+
+impl Summary {
+    // 評価に成功した行の数
+    fn succeeded(&self) -> usize {
+        self.processed - self.failed
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = if self.processed == 1 { "line" } else { "lines" };
+        write!(
+            f,
+            "{} {} processed: {} succeeded, {} failed",
+            self.processed,
+            lines,
+            self.succeeded(),
+            self.failed
+        )
+    }
+}
+
+/**
+ * 指定された数値の種類で計算を実行する
+ */
+fn dispatch<R: BufRead>(input: Input<R>, opts: &Opts, config: Config) -> Result<Summary> {
+    match (opts.mode, opts.int_kind) {
+        (Mode::Int, IntKind::I32) => run::<i32, R>(input, opts, config),
+        (Mode::Int, IntKind::I64) => run::<i64, R>(input, opts, config),
+        (Mode::Int, IntKind::I128) => run::<i128, R>(input, opts, config),
+        (Mode::Int, IntKind::Big) => run::<BigInt, R>(input, opts, config),
+        (Mode::Float, _) => run::<f64, R>(input, opts, config),
+        (Mode::Rational, _) => run::<Rational, R>(input, opts, config),
+        (Mode::Decimal, _) => run::<Decimal, R>(input, opts, config),
+        (Mode::Complex, _) => run::<Complex, R>(input, opts, config),
+    }
+}
+
+/**
+ * 計算機を準備して入力元に応じた処理を実行する
  */
-fn run<R: BufRead>(reader: R, verbose: bool) -> Result<()> {
+fn run<N: Number, R: BufRead>(input: Input<R>, opts: &Opts, config: Config) -> Result<Summary> {
     // RpnCalculator のインスタンスを得る
-    let calc = RpnCalculator::new(verbose);
+    let mut calc = RpnCalculator::<N>::new(config);
+
+    // 保存したセッションを読み込む。設定はセッションのものに置き換わるが、
+    // コマンドラインで明示した設定はそちらを使い、読み込んだ値もその設定で読み直す
+    if let Some(path) = &opts.load_session {
+        calc.load_session(path)?;
+        let mut config = calc.config().clone();
+        opts.apply_explicit(&mut config);
+        calc.set_config(config).with_context(|| {
+            format!("{}: cannot apply the command-line settings", path.display())
+        })?;
+    }
+
+    // プレリュードのワードの定義を読み込む
+    if let Some(path) = &opts.prelude {
+        load_prelude(&mut calc, path)?;
+    }
+
+    // 変数の初期値を設定する
+    for var in &opts.vars {
+        let (name, formula) = var
+            .split_once('=')
+            .with_context(|| format!("--var must be NAME=VALUE: {}", var))?;
+        let value = calc
+            .eval_value(formula)
+            .with_context(|| format!("--var {}", var))?
+            .with_context(|| format!("--var {}: no value", var))?;
+        calc.set_var(name, value);
+    }
+
+    // 対話モードのエラーはその場で直せるので集計しない
+    let summary = match input {
+        Input::Lines(reader) => run_lines(&mut calc, reader, opts)?,
+        Input::Terminal => {
+            repl(&mut calc, opts)?;
+            Summary::default()
+        }
+    };
+
+    // 入力を終えた時点の状態を保存する
+    if let Some(path) = &opts.save_session {
+        calc.save_session(path)?;
+    }
+    Ok(summary)
+}
+
+/**
+ * リーダーで行を取得し計算を実行する処理
+ *
+ * エラーのあった行も報告して次の行に進み、最後に集計を標準エラー出力に書く。
+ * `--fail-fast` の場合は最初のエラーで読むのをやめる
+ */
+fn run_lines<N: Number, R: BufRead>(
+    calc: &mut RpnCalculator<N>,
+    reader: R,
+    opts: &Opts,
+) -> Result<Summary> {
+    let mut errors = StandardStream::stderr(opts.color_choice());
+    // エラーの表示に使う入力元の名前
+    let file = opts
+        .formula_file
+        .as_ref()
+        .map_or_else(|| "<stdin>".to_string(), |path| path.display().to_string());
+
+    let mut summary = Summary::default();
 
     // リーダーを使って1行ずつ処理
-    for line in reader.lines() {
+    for (i, line) in reader.lines().enumerate() {
         // 行を取得
         let line = line?;
-        // 計算の実行
-        match calc.eval(&line) {
-            Ok(answer) => println!("{}", answer),
-            Err(e) => eprintln!("{:#?}", e),
+        // 空白だけの行は読み飛ばす
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.processed += 1;
+
+        // セッションではスタックに積んだまま次の行に進む
+        let res = if opts.session {
+            calc.eval_session(&line)
+        } else {
+            // 計算の実行
+            calc.eval_value(&line).map(|answer| {
+                // 値が残らないのはワードを定義しただけの行
+                if let Some(answer) = answer {
+                    println!("{}", answer.format(calc.config()));
+                }
+            })
+        };
+        if let Err(e) = res {
+            summary.failed += 1;
+            report(calc, &e, &line, &mut errors, Some((&file, i + 1)))?;
+            if opts.fail_fast {
+                break;
+            }
         }
     }
 
-    Ok(())
+    // セッションの最後に残ったスタックを底から順に出力
+    for value in calc.stack() {
+        println!("{}", value.format(calc.config()));
+    }
+
+    writeln!(errors, "{}", summary)?;
+    Ok(summary)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/**
+ * 対話モード
+ *
+ * スタックは行をまたいで引き継ぎ、各行の評価後に表示する。`quit` または Ctrl-D で終了する
+ */
+fn repl<N: Number>(calc: &mut RpnCalculator<N>, opts: &Opts) -> Result<()> {
+    let history = opts
+        .history
+        .clone()
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(HISTORY_FILE)));
+    let mut editor = LineEditor::new(history);
+    let mut errors = StandardStream::stderr(opts.color_choice());
 
-    #[test]
-    fn test_ok() {
-        let calc = RpnCalculator::new(false);
-        assert_eq!(calc.eval("5").unwrap(), 5);
-        assert_eq!(calc.eval("50").unwrap(), 50);
-        assert_eq!(calc.eval("-50").unwrap(), -50);
+    loop {
+        // 定義したワードと変数も補完できるように毎回候補を作り直す
+        editor.set_completions(completions(calc));
+        let Some(line) = editor.read_line(PROMPT)? else {
+            break;
+        };
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        if let Err(e) = calc.eval_session(&line) {
+            report(calc, &e, &line, &mut errors, None)?;
+        }
 
-        assert_eq!(calc.eval("2 3 +").unwrap(), 5);
-        assert_eq!(calc.eval("2 3 *").unwrap(), 6);
-        assert_eq!(calc.eval("2 3 -").unwrap(), -1);
-        assert_eq!(calc.eval("2 3 /").unwrap(), 0);
-        assert_eq!(calc.eval("2 3 %").unwrap(), 2);
+        // スタックを底から順に、先頭を 1 とした段数を付けて表示
+        let depth = calc.stack().len();
+        for (i, value) in calc.stack().iter().enumerate() {
+            println!("{:>3}: {}", depth - i, value.format(calc.config()));
+        }
     }
 
-    #[test]
-    fn test_ng() {
-        let calc = RpnCalculator::new(false);
-        assert!(calc.eval("").is_err());
-        assert!(calc.eval("1 1 1 +").is_err());
-        assert!(calc.eval("+ 1 1").is_err());
+    Ok(())
+}
+
+/**
+ * 評価エラーを入力行と失敗した時点のスタックを添えて表示する
+ *
+ * `location` は入力元の名前と 1 始まりの行番号
+ */
+fn report<N: Number>(
+    calc: &RpnCalculator<N>,
+    error: &EvalError,
+    line: &str,
+    out: &mut StandardStream,
+    location: Option<(&str, usize)>,
+) -> Result<()> {
+    let mut diagnostic = Diagnostic::new(calc, error, line);
+    if let Some((file, number)) = location {
+        diagnostic = diagnostic.with_location(file, number);
     }
+    diagnostic.emit(out)?;
+    Ok(())
+}
+
+/**
+ * 補完の候補にする演算子、ユーザー定義ワード、変数の名前
+ */
+fn completions<N: Number>(calc: &RpnCalculator<N>) -> Vec<String> {
+    let registry = calc.registry();
+    let ops = registry.iter().map(|op| op.name().to_string());
+    let aliases = registry.aliases().map(|(alias, _)| alias.to_string());
+    let words = calc.words().map(|(name, _)| name.to_string());
+    let vars = calc.vars().map(|(name, _)| format!("${}", name));
+    ops.chain(aliases).chain(words).chain(vars).collect()
+}
+
+/**
+ * プレリュードのファイルを 1 行ずつ評価する
+ *
+ * 空行は読み飛ばし、エラーはファイル名と行番号を付けて返す
+ */
+fn load_prelude<N: Number>(calc: &mut RpnCalculator<N>, path: &Path) -> Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        calc.eval_value(&line)
+            .with_context(|| format!("{}:{}", path.display(), i + 1))?;
+    }
+    Ok(())
 }
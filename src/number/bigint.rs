@@ -0,0 +1,728 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use super::{word, Function, Number};
+use crate::config::Config;
+use crate::error::ArithError;
+
+// 10 進数との変換で一度に扱う桁数と、その基数
+const DECIMAL_DIGITS: usize = 9;
+const DECIMAL_BASE: u32 = 1_000_000_000;
+
+/**
+ * 任意精度の符号付き整数
+ *
+ * 絶対値を 2^32 進数のリトルエンディアンで保持する。
+ * 上位桁に 0 は持たず、0 は常に `neg == false` で表す。
+ */
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+impl BigInt {
+    // 0
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    // 1
+    pub fn one() -> Self {
+        Self::from(1i64)
+    }
+
+    // 0 かどうか
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    // 負の数かどうか
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    // 奇数かどうか
+    pub fn is_odd(&self) -> bool {
+        self.mag.first().is_some_and(|d| d & 1 == 1)
+    }
+
+    // 絶対値
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.mag.clone())
+    }
+
+    // 0 方向に切り捨てた商と、被除数と同じ符号の余りを返す。除数が 0 の場合は None
+    pub fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        let (q, r) = div_rem_mag(&self.mag, &rhs.mag);
+        Some((
+            Self::from_parts(self.neg != rhs.neg, q),
+            Self::from_parts(self.neg, r),
+        ))
+    }
+
+    // 最大公約数 (常に 0 以上)
+    pub fn gcd(&self, rhs: &Self) -> Self {
+        let (mut a, mut b) = (self.abs(), rhs.abs());
+        while !b.is_zero() {
+            let (_, r) = a.div_rem(&b).unwrap();
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    // べき乗 (繰り返し二乗法)
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut base = self.clone();
+        let mut acc = Self::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = &acc * &base;
+            }
+            base = &base * &base;
+            exp >>= 1;
+        }
+        acc
+    }
+
+    // 0 以上の整数の平方根の整数部分。負の数の場合は None
+    pub fn isqrt(&self) -> Option<Self> {
+        if self.neg {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(Self::zero());
+        }
+
+        // 平方根以上の 2 のべきから始めてニュートン法で減らしていく
+        let two = Self::from(2i64);
+        let mut x = two.pow(self.bits().div_ceil(2));
+        loop {
+            let (q, _) = self.div_rem(&x).unwrap();
+            let (y, _) = (&x + &q).div_rem(&two).unwrap();
+            if y >= x {
+                return Some(x);
+            }
+            x = y;
+        }
+    }
+
+    // 0 以上 `m` 未満の余り。`m` は正の数であること
+    pub fn rem_euclid(&self, m: &Self) -> Self {
+        let (_, r) = self.div_rem(m).unwrap();
+        if r.is_negative() {
+            &r + m
+        } else {
+            r
+        }
+    }
+
+    // `self^exp mod m` (繰り返し二乗法)。`exp` は 0 以上、`m` は正の数であること
+    pub fn modpow(&self, exp: &Self, m: &Self) -> Self {
+        let mut base = self.rem_euclid(m);
+        let mut acc = Self::one().rem_euclid(m);
+        for i in 0..exp.bits() {
+            if exp.mag[(i / 32) as usize] >> (i % 32) & 1 == 1 {
+                acc = (&acc * &base).rem_euclid(m);
+            }
+            base = (&base * &base).rem_euclid(m);
+        }
+        acc
+    }
+
+    // 法 `m` での逆元。`m` と互いに素でない場合は None
+    pub fn modinv(&self, m: &Self) -> Option<Self> {
+        // 拡張ユークリッドの互除法
+        let (mut old_r, mut r) = (self.rem_euclid(m), m.clone());
+        let (mut old_s, mut s) = (Self::one(), Self::zero());
+        while !r.is_zero() {
+            let (q, _) = old_r.div_rem(&r).unwrap();
+            let next_r = &old_r - &(&q * &r);
+            let next_s = &old_s - &(&q * &s);
+            old_r = std::mem::replace(&mut r, next_r);
+            old_s = std::mem::replace(&mut s, next_s);
+        }
+        (old_r == Self::one()).then(|| old_s.rem_euclid(m))
+    }
+
+    // 絶対値のビット数
+    pub fn bits(&self) -> u32 {
+        match self.mag.last() {
+            Some(top) => self.mag.len() as u32 * 32 - top.leading_zeros(),
+            None => 0,
+        }
+    }
+
+    // 小数部を持たない有限の浮動小数点数から変換する
+    pub fn from_f64(x: f64) -> Option<Self> {
+        if !x.is_finite() || x.fract() != 0.0 {
+            return None;
+        }
+        if x.abs() < i128::MAX as f64 {
+            return Some(Self::from(x as i128));
+        }
+
+        // 仮数部と指数部に分けて 仮数部 * 2^指数部 を求める
+        let bits = x.to_bits();
+        let exp = ((bits >> 52) & 0x7ff) as u32 - 1075;
+        let mant = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let n = &Self::from(mant as i64) * &Self::from(2i64).pow(exp);
+        Some(if x < 0.0 { -&n } else { n })
+    }
+
+    // 最も近い浮動小数点数に変換する
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    // i128 に収まる場合は変換する
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.mag.len() > 4 {
+            return None;
+        }
+        let mag = self
+            .mag
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &d| (acc << 32) | d as u128);
+        if self.neg {
+            0i128.checked_sub_unsigned(mag)
+        } else {
+            i128::try_from(mag).ok()
+        }
+    }
+
+    // 指定した基数で表した絶対値の数字列。`radix` は 2 以上 36 以下であること
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        let mut mag = self.mag.clone();
+        let mut digits = Vec::new();
+        while !mag.is_empty() {
+            let d = div_small(&mut mag, radix);
+            digits.push(
+                std::char::from_digit(d, radix)
+                    .unwrap()
+                    .to_ascii_uppercase(),
+            );
+        }
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        digits.iter().rev().collect()
+    }
+
+    // 10 進数の文字列を解釈する。先頭に符号を付けられる
+    pub fn parse(s: &str) -> Option<Self> {
+        let (neg, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        // 先頭から 9 桁ずつ取り出して 10^9 倍しながら足し込む
+        let mut mag = Vec::new();
+        let head = digits.len() % DECIMAL_DIGITS;
+        let mut chunks = vec![&digits[..head]];
+        chunks.extend(
+            digits.as_bytes()[head..]
+                .chunks(DECIMAL_DIGITS)
+                .map(|c| std::str::from_utf8(c).unwrap()),
+        );
+        for chunk in chunks.into_iter().filter(|c| !c.is_empty()) {
+            let scale = 10u32.pow(chunk.len() as u32);
+            mul_small_add(&mut mag, scale, chunk.parse().ok()?);
+        }
+
+        Some(Self::from_parts(neg, mag))
+    }
+
+    // 符号と絶対値から生成し、正規化する
+    fn from_parts(neg: bool, mut mag: Vec<u32>) -> Self {
+        while mag.last() == Some(&0) {
+            mag.pop();
+        }
+        let neg = neg && !mag.is_empty();
+        Self { neg, mag }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        Self::from(n as i128)
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        let mut abs = n.unsigned_abs();
+        let mut mag = Vec::new();
+        while abs > 0 {
+            mag.push(abs as u32);
+            abs >>= 32;
+        }
+        Self::from_parts(n < 0, mag)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.neg, other.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.neg, self.mag.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        // 同符号なら絶対値の和、異符号なら絶対値の大きい方から小さい方を引く
+        if self.neg == rhs.neg {
+            return BigInt::from_parts(self.neg, add_mag(&self.mag, &rhs.mag));
+        }
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => BigInt::from_parts(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+            _ => BigInt::from_parts(self.neg, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(self.neg != rhs.neg, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 10^9 で割り続けて下位から 9 桁ずつ取り出す
+        let mut mag = self.mag.clone();
+        let mut chunks = Vec::new();
+        while !mag.is_empty() {
+            chunks.push(div_small(&mut mag, DECIMAL_BASE));
+        }
+
+        let mut s = chunks.pop().unwrap_or(0).to_string();
+        for chunk in chunks.iter().rev() {
+            s.push_str(&format!("{:09}", chunk));
+        }
+        f.pad_integral(!self.neg, "", &s)
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Number for BigInt {
+    const KIND: &'static str = "bigint";
+
+    fn parse(token: &str, config: &Config) -> Option<Self> {
+        BigInt::parse(token).or_else(|| word::parse_literal(token, config).map(Self::from))
+    }
+
+    fn out_of_range(token: &str, _config: &Config) -> bool {
+        // ワード長を超える基数付きのリテラル
+        word::is_literal(token)
+    }
+
+    fn from_i64(n: i64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::from(n))
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        self.to_i128()?.try_into().ok()
+    }
+
+    fn to_bigint(&self) -> Option<BigInt> {
+        Some(self.clone())
+    }
+
+    fn from_bigint(n: &BigInt, _config: &Config) -> Result<Self, ArithError> {
+        Ok(n.clone())
+    }
+
+    fn compare(&self, rhs: &Self) -> Result<Ordering, ArithError> {
+        Ok(self.cmp(rhs))
+    }
+
+    fn add(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        let (q, _) = self.div_rem(rhs).ok_or(ArithError::DivisionByZero)?;
+        Ok(q)
+    }
+
+    fn rem(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        let (_, r) = self.div_rem(rhs).ok_or(ArithError::DivisionByZero)?;
+        Ok(r)
+    }
+
+    fn call(&self, func: Function, _config: &Config) -> Result<Self, ArithError> {
+        match func {
+            Function::Neg => Ok(-self),
+            Function::Abs => Ok(self.abs()),
+            Function::Floor | Function::Ceil | Function::Round | Function::Trunc => {
+                Ok(self.clone())
+            }
+            _ => Err(ArithError::Unsupported),
+        }
+    }
+
+    fn format(&self, config: &Config) -> String {
+        word::format_int(self, config)
+    }
+}
+
+/**
+ * 絶対値同士を比較する
+ */
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+/**
+ * 絶対値の和
+ */
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut res = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, &d) in long.iter().enumerate() {
+        let t = d as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        res.push(t as u32);
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        res.push(carry as u32);
+    }
+    res
+}
+
+/**
+ * 絶対値の差。`a >= b` であること
+ */
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &d) in a.iter().enumerate() {
+        let t = d as i64 - *b.get(i).unwrap_or(&0) as i64 + borrow;
+        res.push(t as u32);
+        borrow = t >> 32;
+    }
+    res
+}
+
+/**
+ * 絶対値の積 (筆算)
+ */
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut res = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u64 * y as u64 + res[i + j] as u64 + carry;
+            res[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        res[i + b.len()] = carry as u32;
+    }
+    res
+}
+
+/**
+ * `mag = mag * m + a`
+ */
+fn mul_small_add(mag: &mut Vec<u32>, m: u32, a: u32) {
+    let mut carry = a as u64;
+    for d in mag.iter_mut() {
+        let t = *d as u64 * m as u64 + carry;
+        *d = t as u32;
+        carry = t >> 32;
+    }
+    if carry > 0 {
+        mag.push(carry as u32);
+    }
+}
+
+/**
+ * `mag` を 1 桁の数で割り、商で置き換えて余りを返す
+ */
+fn div_small(mag: &mut Vec<u32>, d: u32) -> u32 {
+    let mut rem = 0u64;
+    for x in mag.iter_mut().rev() {
+        let t = (rem << 32) | *x as u64;
+        *x = (t / d as u64) as u32;
+        rem = t % d as u64;
+    }
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    rem as u32
+}
+
+/**
+ * 絶対値同士の商と余り (Knuth の Algorithm D)。`b` は 0 でないこと
+ */
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_mag(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let mut q = a.to_vec();
+        let r = div_small(&mut q, b[0]);
+        return (q, vec![r]);
+    }
+
+    // 除数の最上位桁の最上位ビットが立つように正規化する
+    let shift = b[b.len() - 1].leading_zeros();
+    let v = shl_bits(b, shift);
+    let mut u = shl_bits(a, shift);
+    u.resize(a.len() + 1, 0);
+    let n = v.len();
+    let m = u.len() - n;
+    let mut q = vec![0u32; m];
+
+    for j in (0..m).rev() {
+        // 上位 2 桁から商の 1 桁を見積もる
+        let num = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut qhat = num / v[n - 1] as u64;
+        let mut rhat = num % v[n - 1] as u64;
+        while qhat > u32::MAX as u64
+            || qhat * v[n - 2] as u64 > ((rhat << 32) | u[j + n - 2] as u64)
+        {
+            qhat -= 1;
+            rhat += v[n - 1] as u64;
+            if rhat > u32::MAX as u64 {
+                break;
+            }
+        }
+
+        // u から qhat * v を引く
+        let mut k = 0i64;
+        for i in 0..n {
+            let p = qhat * v[i] as u64;
+            let t = u[i + j] as i64 - k - (p & 0xffff_ffff) as i64;
+            u[i + j] = t as u32;
+            k = (p >> 32) as i64 - (t >> 32);
+        }
+        let t = u[j + n] as i64 - k;
+        u[j + n] = t as u32;
+
+        // 引きすぎた場合は v を 1 回足し戻す
+        q[j] = qhat as u32;
+        if t < 0 {
+            q[j] = q[j].wrapping_sub(1);
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+    }
+
+    // 余りは正規化した分を戻す
+    u.truncate(n);
+    (q, shr_bits(&u, shift))
+}
+
+/**
+ * 32 未満のビット数だけ左シフトする
+ */
+fn shl_bits(a: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return a.to_vec();
+    }
+    let mut res = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u32;
+    for &d in a {
+        res.push((d << shift) | carry);
+        carry = d >> (32 - shift);
+    }
+    if carry > 0 {
+        res.push(carry);
+    }
+    res
+}
+
+/**
+ * 32 未満のビット数だけ右シフトする
+ */
+fn shr_bits(a: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return a.to_vec();
+    }
+    let mut res = vec![0u32; a.len()];
+    for i in 0..a.len() {
+        let hi = a.get(i + 1).map_or(0, |&d| d << (32 - shift));
+        res[i] = (a[i] >> shift) | hi;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        BigInt::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_display() {
+        for s in [
+            "0",
+            "1",
+            "-1",
+            "4294967296",
+            "-123456789012345678901234567890",
+        ] {
+            assert_eq!(big(s).to_string(), s);
+        }
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("+007").to_string(), "7");
+        assert!(BigInt::parse("").is_none());
+        assert!(BigInt::parse("-").is_none());
+        assert!(BigInt::parse("12a").is_none());
+        assert_eq!(big("255").to_str_radix(16), "FF");
+        assert_eq!(big("-5").to_str_radix(2), "101");
+        assert_eq!(big("0").to_str_radix(8), "0");
+        assert_eq!(
+            big("18446744073709551616").to_str_radix(16),
+            "10000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_arith() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!((&a + &b).to_string(), "-864197532086419753208641975320");
+        assert_eq!((&a - &b).to_string(), "1111111110111111111011111111100");
+        assert_eq!(
+            (&a * &b).to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+        assert_eq!((&a - &a), BigInt::zero());
+        assert!(b < a);
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let (q, r) = big("-121932631137021795226185032733622923332237463801111263526907")
+            .div_rem(&big("987654321098765432109876543210"))
+            .unwrap();
+        assert_eq!(q.to_string(), "-123456789012345678901234567890");
+        assert_eq!(r.to_string(), "-7");
+
+        // i128 の演算結果と突き合わせる
+        let xs = [
+            0i128,
+            1,
+            -7,
+            4294967295,
+            4294967296,
+            -18446744073709551616,
+            170141183460469231731687303715884105727,
+            -99999999999999999999999999,
+        ];
+        for &x in &xs {
+            for &y in xs.iter().filter(|&&y| y != 0) {
+                let (q, r) = BigInt::from(x).div_rem(&BigInt::from(y)).unwrap();
+                assert_eq!(q.to_i128(), x.checked_div(y), "{} / {}", x, y);
+                assert_eq!(r.to_i128(), x.checked_rem(y), "{} % {}", x, y);
+            }
+        }
+        assert!(BigInt::one().div_rem(&BigInt::zero()).is_none());
+    }
+
+    #[test]
+    fn test_number_theory() {
+        assert_eq!(
+            big("1000000000000000000000").isqrt(),
+            Some(big("31622776601"))
+        );
+        assert_eq!(big("15").isqrt(), Some(big("3")));
+        assert_eq!(big("16").isqrt(), Some(big("4")));
+        assert_eq!(big("-1").isqrt(), None);
+        assert_eq!(big("-7").rem_euclid(&big("3")), big("2"));
+        assert_eq!(big("4").modpow(&big("13"), &big("497")), big("445"));
+        assert_eq!(big("3").modinv(&big("11")), Some(big("4")));
+        assert_eq!(big("-3").modinv(&big("11")), Some(big("7")));
+        assert_eq!(big("6").modinv(&big("9")), None);
+        assert_eq!(BigInt::from_f64(1e20), Some(big("100000000000000000000")));
+        assert_eq!(
+            BigInt::from_f64(-2f64.powi(130)).unwrap(),
+            -&big("2").pow(130)
+        );
+        assert_eq!(BigInt::from_f64(0.5), None);
+        assert_eq!(
+            big("-12345678901234567890").to_f64(),
+            -12345678901234567890.0
+        );
+    }
+
+    #[test]
+    fn test_gcd_pow() {
+        assert_eq!(big("-12").gcd(&big("18")), big("6"));
+        assert_eq!(big("0").gcd(&big("-5")), big("5"));
+        assert_eq!(
+            big("10").pow(30).to_string(),
+            "1000000000000000000000000000000"
+        );
+        assert_eq!(big("-2").pow(3), big("-8"));
+        assert_eq!(big("7").pow(0), BigInt::one());
+    }
+}
@@ -0,0 +1,368 @@
+use std::fmt;
+
+use super::{pow_by_squaring, BigInt, Function, Number};
+use crate::config::{ComplexFormat, Config};
+use crate::error::ArithError;
+
+/**
+ * 倍精度浮動小数点数を実部・虚部に持つ複素数
+ *
+ * リテラルは `3+4i`、`-2.5e-1-1i`、`4i`、`i` のような直交形式と実数を受け付ける。
+ * 演算は f64 と同様に IEEE 754 に従い、ゼロ除算はエラーにしない。
+ */
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    // 実部と虚部から生成する
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    // 極形式 (絶対値と偏角) から生成する
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new(r * theta.cos(), r * theta.sin())
+    }
+
+    // 絶対値
+    pub fn norm(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    // 偏角 (ラジアン)
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    // 直交形式で実部と虚部の間に書く符号。NaN は符号ビットに関わらず `+` にする
+    fn im_sign(&self) -> char {
+        if self.im.is_sign_negative() && !self.im.is_nan() {
+            '-'
+        } else {
+            '+'
+        }
+    }
+
+    // 共役複素数
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    // 実数倍
+    fn scale(&self, k: f64) -> Self {
+        Self::new(self.re * k, self.im * k)
+    }
+
+    // 主平方根。極形式を経由しないので、負の実数の平方根は誤差のない純虚数になる
+    fn sqrt(&self) -> Self {
+        let norm = self.norm();
+        let re = ((norm + self.re) / 2.0).sqrt();
+        let im = ((norm - self.re) / 2.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    // 正弦 (引数はラジアン)
+    fn sin(&self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    // 余弦 (引数はラジアン)
+    fn cos(&self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    // 双曲線正弦
+    fn sinh(&self) -> Self {
+        Self::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    // 双曲線余弦
+    fn cosh(&self) -> Self {
+        Self::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    // 自然対数の主値
+    fn ln(&self) -> Self {
+        Self::new(self.norm().ln(), self.arg())
+    }
+
+    // 直交形式の文字列を解釈する
+    fn parse_rect(token: &str) -> Option<Self> {
+        let body = match token.strip_suffix('i') {
+            Some(body) => body,
+            None => return token.parse().ok().map(|re| Self::new(re, 0.0)),
+        };
+
+        // 指数部の符号を除いた最後の `+` または `-` で実部と虚部に分ける
+        let split = body
+            .char_indices()
+            .rev()
+            .find(|&(i, c)| i > 0 && matches!(c, '+' | '-') && !body[..i].ends_with(['e', 'E']))
+            .map(|(i, _)| i);
+        let (re, im) = match split {
+            Some(i) => (body[..i].parse().ok()?, &body[i..]),
+            None => (0.0, body),
+        };
+
+        // 虚部の係数は省略できる (`i`、`1-i`)
+        let im = match im {
+            "" | "+" => 1.0,
+            "-" => -1.0,
+            s => s.parse().ok()?,
+        };
+        Some(Self::new(re, im))
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 実部のみ、虚部のみの場合は片方だけを出力する
+        if self.im == 0.0 {
+            write!(f, "{}", self.re)
+        } else if self.re == 0.0 {
+            write!(f, "{}i", self.im)
+        } else {
+            write!(f, "{}{}{}i", self.re, self.im_sign(), self.im.abs())
+        }
+    }
+}
+
+impl fmt::Debug for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Number for Complex {
+    const KIND: &'static str = "complex";
+
+    fn parse(token: &str, _config: &Config) -> Option<Self> {
+        Self::parse_rect(token)
+    }
+
+    fn from_i64(n: i64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(n as f64, 0.0))
+    }
+
+    fn from_f64(x: f64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(x, 0.0))
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.im != 0.0 {
+            return None;
+        }
+        self.re.to_i64()
+    }
+
+    fn to_bigint(&self) -> Option<BigInt> {
+        if self.im != 0.0 {
+            return None;
+        }
+        BigInt::from_f64(self.re)
+    }
+
+    fn from_bigint(n: &BigInt, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(n.to_f64(), 0.0))
+    }
+
+    fn add(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(self.re + rhs.re, self.im + rhs.im))
+    }
+
+    fn sub(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(self.re - rhs.re, self.im - rhs.im))
+    }
+
+    fn mul(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        ))
+    }
+
+    fn div(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        let d = rhs.re * rhs.re + rhs.im * rhs.im;
+        Ok(Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / d,
+            (self.im * rhs.re - self.re * rhs.im) / d,
+        ))
+    }
+
+    fn rem(&self, _rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    fn pow(&self, exp: &Self, config: &Config) -> Result<Self, ArithError> {
+        // 整数の指数は誤差を抑えるために乗算を繰り返す
+        if let Some(n) = exp.to_i64() {
+            let p = pow_by_squaring(self, n.unsigned_abs(), config)?;
+            return if n < 0 {
+                Self::new(1.0, 0.0).div(&p, config)
+            } else {
+                Ok(p)
+            };
+        }
+        if *self == Self::default() {
+            return Ok(Self::default());
+        }
+        // z^w = exp(w * ln z)
+        let ln = Self::new(self.norm().ln(), self.arg());
+        let p = exp.mul(&ln, config)?;
+        Ok(Self::from_polar(p.re.exp(), p.im))
+    }
+
+    fn call(&self, func: Function, config: &Config) -> Result<Self, ArithError> {
+        // 三角関数は引数を設定の単位の角度として扱う
+        let rad = Self::new(
+            config.angle.radians_of(self.re),
+            config.angle.radians_of(self.im),
+        );
+        let res = match func {
+            Function::Neg => Self::new(-self.re, -self.im),
+            Function::Abs => Self::new(self.norm(), 0.0),
+            // 主値 (偏角を半分にする)
+            Function::Sqrt => self.sqrt(),
+            Function::Conj => self.conj(),
+            Function::Arg => Self::new(config.angle.of_radians(self.arg()), 0.0),
+            Function::Re => Self::new(self.re, 0.0),
+            Function::Im => Self::new(self.im, 0.0),
+            Function::Sin => rad.sin(),
+            Function::Cos => rad.cos(),
+            Function::Tan => rad.sin().div(&rad.cos(), config)?,
+            Function::Sinh => self.sinh(),
+            Function::Cosh => self.cosh(),
+            Function::Tanh => self.sinh().div(&self.cosh(), config)?,
+            Function::Exp => Self::from_polar(self.re.exp(), self.im),
+            Function::Ln => self.ln(),
+            Function::Log10 => self.ln().scale(1.0 / std::f64::consts::LN_10),
+            Function::Log2 => self.ln().scale(1.0 / std::f64::consts::LN_2),
+            Function::Asin
+            | Function::Acos
+            | Function::Atan
+            | Function::Floor
+            | Function::Ceil
+            | Function::Round
+            | Function::Trunc => return Err(ArithError::Unsupported),
+        };
+        Ok(res)
+    }
+
+    fn cplx(&self, im: &Self, _config: &Config) -> Result<Self, ArithError> {
+        // 実部と虚部はそれぞれの実部を使う
+        Ok(Self::new(self.re, im.re))
+    }
+
+    fn format(&self, config: &Config) -> String {
+        let num = |x: f64| match config.clamped_precision() {
+            Some(p) => format!("{:.*}", p, x),
+            None => x.to_string(),
+        };
+        match config.complex_format {
+            ComplexFormat::Rect if config.precision.is_none() => self.to_string(),
+            ComplexFormat::Rect => {
+                format!("{}{}{}i", num(self.re), self.im_sign(), num(self.im.abs()))
+            }
+            // 偏角は三角関数と同じく設定の単位で表す
+            ComplexFormat::Polar => format!(
+                "{}∠{}",
+                num(self.norm()),
+                num(config.angle.of_radians(self.arg()))
+            ),
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Angle;
+
+    fn c(s: &str) -> Complex {
+        Complex::parse(s, &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(c("3+4i"), Complex::new(3.0, 4.0));
+        assert_eq!(c("3-4i"), Complex::new(3.0, -4.0));
+        assert_eq!(c("-2.5e-1-1e2i"), Complex::new(-0.25, -100.0));
+        assert_eq!(c("4i"), Complex::new(0.0, 4.0));
+        assert_eq!(c("-i"), Complex::new(0.0, -1.0));
+        assert_eq!(c("1+i"), Complex::new(1.0, 1.0));
+        assert_eq!(c("1e+3"), Complex::new(1000.0, 0.0));
+        for s in ["+", "-", "pi", "ii", "3+4j"] {
+            assert!(Complex::parse(s, &Config::default()).is_none(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_arith() {
+        let config = Config::default();
+        assert_eq!(c("1+2i").mul(&c("3+4i"), &config).unwrap(), c("-5+10i"));
+        assert_eq!(c("-5+10i").div(&c("3+4i"), &config).unwrap(), c("1+2i"));
+        assert_eq!(c("3+4i").call(Function::Abs, &config).unwrap(), c("5"));
+        assert_eq!(c("3+4i").call(Function::Conj, &config).unwrap(), c("3-4i"));
+        assert_eq!(c("3").cplx(&c("4"), &config).unwrap(), c("3+4i"));
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let config = Config::default();
+        let sqrt = |s: &str| c(s).call(Function::Sqrt, &config).unwrap();
+        assert_eq!(sqrt("-1"), c("i"));
+        assert_eq!(sqrt("-4"), c("2i"));
+        assert_eq!(sqrt("-1").format(&config), "1i");
+        assert_eq!(sqrt("4"), c("2"));
+        assert_eq!(sqrt("3+4i"), c("2+i"));
+        assert_eq!(sqrt("3-4i"), c("2-i"));
+    }
+
+    #[test]
+    fn test_format() {
+        let mut config = Config::default();
+        assert_eq!(c("3-4i").format(&config), "3-4i");
+        assert_eq!(c("-4i").format(&config), "-4i");
+        config.precision = Some(2);
+        assert_eq!(c("1+2i").format(&config), "1.00+2.00i");
+        config.complex_format = ComplexFormat::Polar;
+        assert_eq!(c("i").format(&config), "1.00∠1.57");
+        config.angle = Angle::Deg;
+        assert_eq!(c("-1-i").format(&config), "1.41∠-135.00");
+        assert_eq!(c("i").call(Function::Arg, &config).unwrap(), c("90"));
+    }
+
+    #[test]
+    fn test_nan() {
+        // 符号ビットの立った NaN でも実部と虚部の間に `+` を書き、読み戻せる
+        let z = Complex::new(f64::NAN, -f64::NAN);
+        assert_eq!(z.to_string(), "NaN+NaNi");
+        let config = Config {
+            precision: Some(1),
+            ..Config::default()
+        };
+        assert_eq!(z.format(&config), "NaN+NaNi");
+        let back = c(&z.to_string());
+        assert!(back.re.is_nan() && back.im.is_nan());
+        assert_eq!(c("1-2i").to_string(), "1-2i");
+    }
+}
@@ -0,0 +1,419 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::{check_pow_size, pow_by_squaring, BigInt, Function, Number};
+use crate::config::{Config, Rounding};
+use crate::error::ArithError;
+
+/**
+ * 固定小数点の 10 進数
+ *
+ * `mant / 10^scale` を表す。小数点以下の桁数は設定の `scale` で決まり、
+ * 乗除算とリテラルの桁あふれは設定の `rounding` に従って丸める。
+ * 出力は末尾の 0 を残して常に `scale` 桁表示する。
+ * 設定の `scale` が `MAX_SCALE` を超える場合は数値を作れず、演算はエラーになる。
+ * 比較は桁数によらず値で行うので、`1.5` と `1.5000` は等しい。
+ */
+#[derive(Clone)]
+pub struct Decimal {
+    mant: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    // 仮数部と小数点以下の桁数から生成する
+    pub fn new(mant: BigInt, scale: u32) -> Self {
+        Self { mant, scale }
+    }
+
+    // 仮数部
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mant
+    }
+
+    // 小数点以下の桁数
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    // 小数点以下の桁数を変更する。桁を減らす場合は丸める
+    pub fn rescale(&self, scale: u32, rounding: Rounding) -> Self {
+        let mant = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.mant.clone(),
+            Ordering::Greater => &self.mant * &pow10(scale - self.scale),
+            Ordering::Less => round_div(&self.mant, &pow10(self.scale - scale), rounding),
+        };
+        Self { mant, scale }
+    }
+
+    // 末尾の 0 を取り除いた仮数部と桁数。値が等しければ桁数によらず同じになる
+    fn normalized(&self) -> (BigInt, u32) {
+        let ten = BigInt::from(10i64);
+        let (mut mant, mut scale) = (self.mant.clone(), self.scale);
+        while scale > 0 {
+            let (q, r) = mant.div_rem(&ten).unwrap();
+            if !r.is_zero() {
+                break;
+            }
+            mant = q;
+            scale -= 1;
+        }
+        (mant, scale)
+    }
+
+    // 小数点以下の桁数を保ったまま整数に丸める
+    fn round_to_integer(&self, rounding: Rounding) -> Self {
+        self.rescale(0, rounding).rescale(self.scale, rounding)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 桁数の多い方に揃えて仮数部を比べる
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (
+            self.rescale(scale, Rounding::Down),
+            other.rescale(scale, Rounding::Down),
+        );
+        a.mant.cmp(&b.mant)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 書式の幅は大きな桁数を扱えないので、足りない 0 は自前で補う
+        let scale = self.scale as usize;
+        let mant = self.mant.abs().to_string();
+        let zeros = (scale + 1).saturating_sub(mant.len());
+        let digits = format!("{}{}", "0".repeat(zeros), mant);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let sign = if self.mant.is_negative() { "-" } else { "" };
+        if scale == 0 {
+            write!(f, "{}{}", sign, int)
+        } else {
+            write!(f, "{}{}.{}", sign, int, frac)
+        }
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Number for Decimal {
+    const KIND: &'static str = "decimal";
+
+    fn parse(token: &str, config: &Config) -> Option<Self> {
+        // 小数点の位置からリテラル自身の桁数を求め、設定の桁数に合わせる
+        let (int, frac) = token.split_once('.').unwrap_or((token, ""));
+        if !frac.bytes().all(|b| b.is_ascii_digit()) || (token.contains('.') && frac.is_empty()) {
+            return None;
+        }
+        let scale = config.checked_scale()?;
+        let mant = BigInt::parse(&format!("{}{}", int, frac))?;
+        Some(Self::new(mant, frac.len() as u32).rescale(scale, config.rounding))
+    }
+
+    fn from_i64(n: i64, config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(BigInt::from(n), 0).rescale(scale(config)?, config.rounding))
+    }
+
+    fn from_f64(x: f64, config: &Config) -> Result<Self, ArithError> {
+        // 最短の 10 進表記を設定の桁数に丸める
+        if !x.is_finite() {
+            return Err(ArithError::Domain);
+        }
+        Self::parse(&format!("{:?}", x), config).ok_or(ArithError::Domain)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        let (q, r) = self.mant.div_rem(&pow10(self.scale)).unwrap();
+        if !r.is_zero() {
+            return None;
+        }
+        q.to_i128()?.try_into().ok()
+    }
+
+    fn to_bigint(&self) -> Option<BigInt> {
+        let (q, r) = self.mant.div_rem(&pow10(self.scale)).unwrap();
+        r.is_zero().then_some(q)
+    }
+
+    fn from_bigint(n: &BigInt, config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(n.clone(), 0).rescale(scale(config)?, config.rounding))
+    }
+
+    fn compare(&self, rhs: &Self) -> Result<Ordering, ArithError> {
+        Ok(self.cmp(rhs))
+    }
+
+    fn add(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        let scale = scale(config)?;
+        let (x, y) = (
+            self.rescale(scale, config.rounding),
+            rhs.rescale(scale, config.rounding),
+        );
+        Ok(Self::new(&x.mant + &y.mant, scale))
+    }
+
+    fn sub(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        let scale = scale(config)?;
+        let (x, y) = (
+            self.rescale(scale, config.rounding),
+            rhs.rescale(scale, config.rounding),
+        );
+        Ok(Self::new(&x.mant - &y.mant, scale))
+    }
+
+    fn mul(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        // 積の桁数は両者の桁数の和になるので、設定の桁数まで丸める
+        let prod = Self::new(&self.mant * &rhs.mant, self.scale + rhs.scale);
+        Ok(prod.rescale(scale(config)?, config.rounding))
+    }
+
+    fn div(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        if rhs.mant.is_zero() {
+            return Err(ArithError::DivisionByZero);
+        }
+        // (x / 10^a) / (y / 10^b) = x * 10^(scale + b - a) / y / 10^scale
+        let scale = scale(config)?;
+        let shift = scale + rhs.scale;
+        let num = if shift >= self.scale {
+            &self.mant * &pow10(shift - self.scale)
+        } else {
+            round_div(&self.mant, &pow10(self.scale - shift), config.rounding)
+        };
+        Ok(Self::new(
+            round_div(&num, &rhs.mant, config.rounding),
+            scale,
+        ))
+    }
+
+    fn rem(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        let scale = scale(config)?;
+        let (x, y) = (
+            self.rescale(scale, config.rounding),
+            rhs.rescale(scale, config.rounding),
+        );
+        let (_, r) = x.mant.div_rem(&y.mant).ok_or(ArithError::DivisionByZero)?;
+        Ok(Self::new(r, scale))
+    }
+
+    fn pow(&self, exp: &Self, config: &Config) -> Result<Self, ArithError> {
+        // 負の整数の指数は逆数のべき乗にする
+        let exp = exp.to_i64().ok_or(ArithError::Domain)?;
+        // 絶対値が 1 より大きい底は、設定の桁数に揃えた仮数を丸めずに掛けた場合の
+        // ビット数で結果の大きさを抑える。1 に近い底でも小数部の桁の分だけ仮数は伸びるので、
+        // 絶対値の対数では見積もれない。1 以下の底は掛けるたびに丸めるので仮数は伸びない
+        let base = self.rescale(scale(config)?, config.rounding);
+        if base.mant.abs() > pow10(base.scale) {
+            check_pow_size(base.mant.bits() as f64, exp.unsigned_abs())?;
+        }
+        let p = pow_by_squaring(&base, exp.unsigned_abs(), config)?;
+        if exp < 0 {
+            Self::from_i64(1, config)?.div(&p, config)
+        } else {
+            Ok(p)
+        }
+    }
+
+    fn call(&self, func: Function, _config: &Config) -> Result<Self, ArithError> {
+        match func {
+            Function::Neg => Ok(Self::new(-&self.mant, self.scale)),
+            Function::Abs => Ok(Self::new(self.mant.abs(), self.scale)),
+            Function::Floor => Ok(self.round_to_integer(Rounding::Floor)),
+            Function::Ceil => Ok(self.round_to_integer(Rounding::Ceiling)),
+            Function::Round => Ok(self.round_to_integer(Rounding::HalfUp)),
+            Function::Trunc => Ok(self.round_to_integer(Rounding::Down)),
+            _ => Err(ArithError::Unsupported),
+        }
+    }
+}
+
+/**
+ * 設定の小数点以下の桁数。上限を超える場合はエラー
+ */
+fn scale(config: &Config) -> Result<u32, ArithError> {
+    config.checked_scale().ok_or(ArithError::Domain)
+}
+
+/**
+ * 10^n
+ */
+fn pow10(n: u32) -> BigInt {
+    BigInt::from(10i64).pow(n)
+}
+
+/**
+ * 丸め方に従って `n / d` を整数に丸める。`d` は 0 でないこと
+ */
+fn round_div(n: &BigInt, d: &BigInt, rounding: Rounding) -> BigInt {
+    let (q, r) = n.div_rem(d).unwrap();
+    if r.is_zero() {
+        return q;
+    }
+
+    // 真の商の符号と、端数が 1/2 と比べてどうか
+    let neg = n.is_negative() != d.is_negative();
+    let r2 = &r.abs() + &r.abs();
+    let half = r2.cmp(&d.abs());
+    let away = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => true,
+        Rounding::Ceiling => !neg,
+        Rounding::Floor => neg,
+        Rounding::HalfUp => half != Ordering::Less,
+        Rounding::HalfEven => half == Ordering::Greater || (half == Ordering::Equal && q.is_odd()),
+    };
+
+    if !away {
+        q
+    } else if neg {
+        &q - &BigInt::one()
+    } else {
+        &q + &BigInt::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAX_SCALE;
+
+    fn config(scale: u32, rounding: Rounding) -> Config {
+        Config {
+            scale,
+            rounding,
+            ..Config::default()
+        }
+    }
+
+    fn dec(s: &str, config: &Config) -> Decimal {
+        Decimal::parse(s, config).unwrap()
+    }
+
+    #[test]
+    fn test_parse_display() {
+        let c = config(2, Rounding::HalfEven);
+        assert_eq!(dec("1", &c).to_string(), "1.00");
+        assert_eq!(dec("-0.5", &c).to_string(), "-0.50");
+        assert_eq!(dec("1.005", &c).to_string(), "1.00");
+        assert_eq!(dec("1.015", &c).to_string(), "1.02");
+        assert_eq!(
+            dec("12.3456", &config(0, Rounding::HalfEven)).to_string(),
+            "12"
+        );
+        assert!(Decimal::parse("1.", &c).is_none());
+        assert!(Decimal::parse("1.2.3", &c).is_none());
+        assert!(Decimal::parse("+", &c).is_none());
+    }
+
+    #[test]
+    fn test_rounding() {
+        // 各丸め方での 1 / 8 = 0.125 と -1 / 8 = -0.125 (小数点以下 2 桁)
+        let cases = [
+            (Rounding::HalfEven, "0.12", "-0.12"),
+            (Rounding::HalfUp, "0.13", "-0.13"),
+            (Rounding::Down, "0.12", "-0.12"),
+            (Rounding::Up, "0.13", "-0.13"),
+            (Rounding::Ceiling, "0.13", "-0.12"),
+            (Rounding::Floor, "0.12", "-0.13"),
+        ];
+        for (rounding, pos, neg) in cases {
+            let c = config(2, rounding);
+            let eight = dec("8", &c);
+            assert_eq!(dec("1", &c).div(&eight, &c).unwrap().to_string(), pos);
+            assert_eq!(dec("-1", &c).div(&eight, &c).unwrap().to_string(), neg);
+        }
+    }
+
+    #[test]
+    fn test_arith() {
+        let c = config(2, Rounding::HalfEven);
+        assert_eq!(
+            dec("0.10", &c)
+                .add(&dec("0.20", &c), &c)
+                .unwrap()
+                .to_string(),
+            "0.30"
+        );
+        assert_eq!(
+            dec("1.25", &c)
+                .mul(&dec("1.25", &c), &c)
+                .unwrap()
+                .to_string(),
+            "1.56"
+        );
+        assert_eq!(
+            dec("10", &c).div(&dec("3", &c), &c).unwrap().to_string(),
+            "3.33"
+        );
+        assert_eq!(
+            dec("7.5", &c).rem(&dec("2", &c), &c).unwrap().to_string(),
+            "1.50"
+        );
+        assert_eq!(
+            dec("1", &c).div(&dec("0", &c), &c),
+            Err(ArithError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_mixed_scale() {
+        // 桁数の異なる同じ値は等しく、ハッシュも同じ
+        let a = dec("1.5", &config(2, Rounding::HalfEven));
+        let b = dec("1.5", &config(4, Rounding::HalfEven));
+        assert_eq!(a.to_string(), "1.50");
+        assert_eq!(b.to_string(), "1.5000");
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        let hash = |x: &Decimal| {
+            let mut h = std::collections::hash_map::DefaultHasher::new();
+            x.hash(&mut h);
+            h.finish()
+        };
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(a, dec("1.51", &config(4, Rounding::HalfEven)));
+        assert_eq!(
+            dec("0", &config(0, Rounding::HalfEven)),
+            dec("0.000", &config(3, Rounding::HalfEven))
+        );
+    }
+
+    #[test]
+    fn test_scale_limit() {
+        // 上限を超える桁数の設定では数値を作れない
+        let c = config(MAX_SCALE + 1, Rounding::HalfEven);
+        assert!(Decimal::parse("1", &c).is_none());
+        assert_eq!(Decimal::from_i64(1, &c), Err(ArithError::Domain));
+        let one = dec("1", &config(2, Rounding::HalfEven));
+        assert_eq!(one.add(&one, &c), Err(ArithError::Domain));
+
+        // 書式の幅の上限より多い桁数でも表示できる
+        let tiny = Decimal::new(BigInt::one(), 70000);
+        assert_eq!(tiny.to_string().len(), "0.".len() + 70000);
+    }
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+
+use super::{BigInt, Function, Number};
+use crate::config::{Config, FloatFormat};
+use crate::error::ArithError;
+
+// 自動選択で指数表記に切り替える指数の範囲
+const AUTO_SCI_MIN_EXP: i32 = -4;
+const AUTO_SCI_MAX_EXP: i32 = 15;
+
+/**
+ * 倍精度浮動小数点数
+ *
+ * 演算は IEEE 754 に従う。ゼロ除算は無限大 (`0 0 /` は NaN) になり、
+ * NaN や無限大はエラーにせずそのまま後続の演算に伝播させる。
+ * リテラルは `1.5`、`1.5e-3` のほか `inf`、`-inf`、`nan` を受け付ける。
+ */
+impl Number for f64 {
+    const KIND: &'static str = "float";
+
+    fn parse(token: &str, _config: &Config) -> Option<Self> {
+        token.parse().ok()
+    }
+
+    fn from_i64(n: i64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(n as f64)
+    }
+
+    fn from_f64(x: f64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(x)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        // 小数部を持たず i64 の範囲に収まる場合のみ
+        let in_range = (i64::MIN as f64..i64::MAX as f64).contains(self);
+        (self.fract() == 0.0 && in_range).then_some(*self as i64)
+    }
+
+    fn to_bigint(&self) -> Option<BigInt> {
+        BigInt::from_f64(*self)
+    }
+
+    fn from_bigint(n: &BigInt, _config: &Config) -> Result<Self, ArithError> {
+        Ok(n.to_f64())
+    }
+
+    fn compare(&self, rhs: &Self) -> Result<Ordering, ArithError> {
+        // NaN とは比較できない
+        self.partial_cmp(rhs).ok_or(ArithError::Domain)
+    }
+
+    fn add(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self / rhs)
+    }
+
+    fn rem(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self % rhs)
+    }
+
+    fn pow(&self, exp: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(self.powf(*exp))
+    }
+
+    fn atan2(&self, x: &Self, config: &Config) -> Result<Self, ArithError> {
+        Ok(config.angle.of_radians(f64::atan2(*self, *x)))
+    }
+
+    fn call(&self, func: Function, config: &Config) -> Result<Self, ArithError> {
+        // 三角関数は設定の単位の角度を受け取り、逆三角関数は設定の単位で返す
+        let angle = config.angle;
+        let res = match func {
+            Function::Neg => -self,
+            Function::Abs => self.abs(),
+            Function::Sqrt => self.sqrt(),
+            Function::Sin => angle.radians_of(*self).sin(),
+            Function::Cos => angle.radians_of(*self).cos(),
+            Function::Tan => angle.radians_of(*self).tan(),
+            Function::Asin => angle.of_radians(self.asin()),
+            Function::Acos => angle.of_radians(self.acos()),
+            Function::Atan => angle.of_radians(self.atan()),
+            Function::Sinh => self.sinh(),
+            Function::Cosh => self.cosh(),
+            Function::Tanh => self.tanh(),
+            Function::Exp => self.exp(),
+            Function::Ln => self.ln(),
+            Function::Log10 => self.log10(),
+            Function::Log2 => self.log2(),
+            Function::Floor => self.floor(),
+            Function::Ceil => self.ceil(),
+            Function::Round => self.round(),
+            Function::Trunc => self.trunc(),
+            Function::Conj | Function::Arg | Function::Re | Function::Im => {
+                return Err(ArithError::Unsupported)
+            }
+        };
+        Ok(res)
+    }
+
+    fn format(&self, config: &Config) -> String {
+        // NaN と無限大は表記によらず同じ
+        if !self.is_finite() {
+            return self.to_string();
+        }
+
+        let sci = match config.float_format {
+            FloatFormat::Fixed => false,
+            FloatFormat::Sci => true,
+            FloatFormat::Auto => {
+                let exp = self.abs().log10().floor() as i32;
+                *self != 0.0 && !(AUTO_SCI_MIN_EXP..AUTO_SCI_MAX_EXP).contains(&exp)
+            }
+        };
+
+        match (sci, config.clamped_precision()) {
+            (false, None) => self.to_string(),
+            (false, Some(p)) => format!("{:.*}", p, self),
+            (true, None) => format!("{:e}", self),
+            (true, Some(p)) => format!("{:.*e}", p, self),
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAX_PRECISION;
+
+    fn format(x: f64, float_format: FloatFormat, precision: Option<usize>) -> String {
+        let config = Config {
+            float_format,
+            precision,
+            ..Config::default()
+        };
+        x.format(&config)
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(0.5, FloatFormat::Auto, None), "0.5");
+        assert_eq!(format(1.0 / 3.0, FloatFormat::Auto, Some(3)), "0.333");
+        assert_eq!(format(0.0, FloatFormat::Auto, None), "0");
+        assert_eq!(format(1.5e-7, FloatFormat::Auto, None), "1.5e-7");
+        assert_eq!(format(2e20, FloatFormat::Auto, Some(2)), "2.00e20");
+        assert_eq!(
+            format(2e20, FloatFormat::Fixed, None),
+            "200000000000000000000"
+        );
+        assert_eq!(format(1234.5, FloatFormat::Sci, None), "1.2345e3");
+        assert_eq!(format(1234.5, FloatFormat::Fixed, Some(0)), "1234");
+        assert_eq!(format(f64::NEG_INFINITY, FloatFormat::Sci, Some(2)), "-inf");
+        assert_eq!(format(f64::NAN, FloatFormat::Fixed, None), "NaN");
+
+        // 上限を超える桁数は上限に切り詰める
+        let long = format(1.0 / 3.0, FloatFormat::Fixed, Some(70000));
+        assert_eq!(long.len(), "0.".len() + MAX_PRECISION);
+    }
+}
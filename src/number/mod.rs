@@ -0,0 +1,301 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::config::{Arithmetic, Config};
+use crate::error::ArithError;
+
+mod bigint;
+mod complex;
+mod decimal;
+mod float;
+mod rational;
+mod word;
+
+pub use bigint::BigInt;
+pub use complex::Complex;
+pub use decimal::Decimal;
+pub use rational::Rational;
+pub(crate) use word::{from_bits, to_bits};
+
+// 任意精度の数値で求める結果の大きさの上限 (ビット数)。約 7.9 万桁
+pub(crate) const MAX_RESULT_BITS: u64 = 1 << 18;
+
+/**
+ * 計算機のスタックに積む数値型
+ *
+ * 演算は設定を受け取り、ゼロ除算やオーバーフローを `ArithError` で返す。
+ */
+pub trait Number: Clone + PartialEq + fmt::Debug + fmt::Display + 'static {
+    // 数値の種類の名前。保存したセッションを読み込むときに照合する
+    const KIND: &'static str;
+
+    // トークンを数値として解釈する。数値でなければ None
+    fn parse(token: &str, config: &Config) -> Option<Self>;
+
+    // `parse` で解釈できなかったトークンが、数値の書式でありながら範囲外かどうか
+    fn out_of_range(_token: &str, _config: &Config) -> bool {
+        false
+    }
+
+    // 整数から変換する
+    fn from_i64(n: i64, config: &Config) -> Result<Self, ArithError>;
+
+    // 浮動小数点数から変換する。表せない種類の数値では `ArithError::Unsupported`
+    fn from_f64(_x: f64, _config: &Config) -> Result<Self, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    // 整数値で i64 に収まる場合は変換する
+    fn to_i64(&self) -> Option<i64>;
+
+    // 整数値の場合は任意精度整数に変換する
+    fn to_bigint(&self) -> Option<BigInt>;
+
+    // 任意精度整数から変換する。表せない場合は `ArithError::Overflow`
+    fn from_bigint(n: &BigInt, config: &Config) -> Result<Self, ArithError>;
+
+    // 和
+    fn add(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError>;
+
+    // 差
+    fn sub(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError>;
+
+    // 積
+    fn mul(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError>;
+
+    // 商
+    fn div(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError>;
+
+    // 剰余
+    fn rem(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError>;
+
+    // べき乗。指数が 0 以上の整数の場合に乗算を繰り返して求める
+    fn pow(&self, exp: &Self, config: &Config) -> Result<Self, ArithError> {
+        let exp = exp.to_i64().ok_or(ArithError::Domain)?;
+        let exp = u64::try_from(exp).map_err(|_| ArithError::Domain)?;
+        let bits = self.to_bigint().map_or(0, |n| n.bits().saturating_sub(1));
+        check_pow_size(bits as f64, exp)?;
+        pow_by_squaring(self, exp, config)
+    }
+
+    // 単項の関数を適用する。対応していない関数は `ArithError::Unsupported`
+    fn call(&self, _func: Function, _config: &Config) -> Result<Self, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    // 点 (x, y) の偏角。`self` が y 座標
+    fn atan2(&self, _x: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    // 大小を比較する。順序を持たない種類の数値では `ArithError::Unsupported`
+    fn compare(&self, _rhs: &Self) -> Result<Ordering, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    // 実部と虚部から複素数を作る
+    fn cplx(&self, _im: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Err(ArithError::Unsupported)
+    }
+
+    // 計算結果を出力用に整形する
+    fn format(&self, _config: &Config) -> String {
+        self.to_string()
+    }
+
+    // 無限大や NaN でないかどうか。オーバーフローしても誤差にならない種類の数値で使う
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+/**
+ * 繰り返し二乗法によるべき乗
+ *
+ * 不要な二乗は行わないので、結果が収まる場合は途中でオーバーフローしない
+ */
+pub(crate) fn pow_by_squaring<N: Number>(
+    x: &N,
+    mut exp: u64,
+    config: &Config,
+) -> Result<N, ArithError> {
+    let mut acc = N::from_i64(1, config)?;
+    let mut base = x.clone();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = acc.mul(&base, config)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.mul(&base, config)?;
+        }
+    }
+    Ok(acc)
+}
+
+/**
+ * べき乗の結果が大きくなりすぎないか確かめる
+ *
+ * `bits` は 1 回掛けるごとに増えるビット数の見積もり。任意精度の数値では
+ * 計算を始める前に弾かないと時間とメモリを使い果たすので `ArithError::Overflow` にする
+ */
+pub(crate) fn check_pow_size(bits: f64, exp: u64) -> Result<(), ArithError> {
+    if bits * exp as f64 > MAX_RESULT_BITS as f64 {
+        Err(ArithError::Overflow)
+    } else {
+        Ok(())
+    }
+}
+
+/**
+ * 数値に適用する単項の関数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    // 符号反転
+    Neg,
+    // 絶対値
+    Abs,
+    // 平方根
+    Sqrt,
+    // 共役複素数
+    Conj,
+    // 偏角 (ラジアン)
+    Arg,
+    // 実部
+    Re,
+    // 虚部
+    Im,
+    // 三角関数
+    Sin,
+    Cos,
+    Tan,
+    // 逆三角関数
+    Asin,
+    Acos,
+    Atan,
+    // 双曲線関数
+    Sinh,
+    Cosh,
+    Tanh,
+    // 指数関数と対数関数
+    Exp,
+    Ln,
+    Log10,
+    Log2,
+    // 整数への丸め
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+}
+
+/**
+ * 固定長の整数型に Number を実装する
+ */
+macro_rules! impl_number_for_int {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const KIND: &'static str = stringify!($t);
+
+            fn parse(token: &str, config: &Config) -> Option<Self> {
+                token
+                    .parse()
+                    .ok()
+                    .or_else(|| word::parse_literal(token, config)?.try_into().ok())
+            }
+
+            fn out_of_range(token: &str, _config: &Config) -> bool {
+                BigInt::parse(token).is_some() || word::is_literal(token)
+            }
+
+            fn from_i64(n: i64, _config: &Config) -> Result<Self, ArithError> {
+                n.try_into().map_err(|_| ArithError::Overflow)
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                (*self).try_into().ok()
+            }
+
+            fn to_bigint(&self) -> Option<BigInt> {
+                Some(BigInt::from(*self as i128))
+            }
+
+            fn from_bigint(n: &BigInt, _config: &Config) -> Result<Self, ArithError> {
+                n.to_i128()
+                    .and_then(|n| n.try_into().ok())
+                    .ok_or(ArithError::Overflow)
+            }
+
+            fn compare(&self, rhs: &Self) -> Result<Ordering, ArithError> {
+                Ok(self.cmp(rhs))
+            }
+
+            fn add(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+                match config.arithmetic {
+                    Arithmetic::Checked => self.checked_add(*rhs).ok_or(ArithError::Overflow),
+                    Arithmetic::Wrapping => Ok(self.wrapping_add(*rhs)),
+                    Arithmetic::Saturating => Ok(self.saturating_add(*rhs)),
+                }
+            }
+
+            fn sub(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+                match config.arithmetic {
+                    Arithmetic::Checked => self.checked_sub(*rhs).ok_or(ArithError::Overflow),
+                    Arithmetic::Wrapping => Ok(self.wrapping_sub(*rhs)),
+                    Arithmetic::Saturating => Ok(self.saturating_sub(*rhs)),
+                }
+            }
+
+            fn mul(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+                match config.arithmetic {
+                    Arithmetic::Checked => self.checked_mul(*rhs).ok_or(ArithError::Overflow),
+                    Arithmetic::Wrapping => Ok(self.wrapping_mul(*rhs)),
+                    Arithmetic::Saturating => Ok(self.saturating_mul(*rhs)),
+                }
+            }
+
+            fn div(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+                // ゼロ除算はどの演算方法でもエラー
+                if *rhs == 0 {
+                    return Err(ArithError::DivisionByZero);
+                }
+                match config.arithmetic {
+                    Arithmetic::Checked => self.checked_div(*rhs).ok_or(ArithError::Overflow),
+                    Arithmetic::Wrapping => Ok(self.wrapping_div(*rhs)),
+                    Arithmetic::Saturating => Ok(self.saturating_div(*rhs)),
+                }
+            }
+
+            fn rem(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+                if *rhs == 0 {
+                    return Err(ArithError::DivisionByZero);
+                }
+                match config.arithmetic {
+                    Arithmetic::Checked => self.checked_rem(*rhs).ok_or(ArithError::Overflow),
+                    // 剰余は飽和させても値が変わらないので折り返しと同じ
+                    _ => Ok(self.wrapping_rem(*rhs)),
+                }
+            }
+
+            fn call(&self, func: Function, config: &Config) -> Result<Self, ArithError> {
+                match (func, config.arithmetic) {
+                    (Function::Neg, Arithmetic::Checked) => self.checked_neg().ok_or(ArithError::Overflow),
+                    (Function::Neg, Arithmetic::Wrapping) => Ok(self.wrapping_neg()),
+                    (Function::Neg, Arithmetic::Saturating) => Ok(self.saturating_neg()),
+                    (Function::Abs, Arithmetic::Checked) => self.checked_abs().ok_or(ArithError::Overflow),
+                    (Function::Abs, Arithmetic::Wrapping) => Ok(self.wrapping_abs()),
+                    (Function::Abs, Arithmetic::Saturating) => Ok(self.saturating_abs()),
+                    (Function::Floor | Function::Ceil | Function::Round | Function::Trunc, _) => Ok(*self),
+                    _ => Err(ArithError::Unsupported),
+                }
+            }
+
+            fn format(&self, config: &Config) -> String {
+                word::format_int(&BigInt::from(*self as i128), config)
+            }
+        }
+    )*};
+}
+
+impl_number_for_int!(i32, i64, i128);
@@ -0,0 +1,333 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::{check_pow_size, pow_by_squaring, BigInt, Function, Number};
+use crate::config::{Config, RationalFormat};
+use crate::error::ArithError;
+
+// 小数表記で桁数の指定がない場合の小数点以下の桁数
+const DEFAULT_DECIMAL_PLACES: usize = 10;
+
+/**
+ * 任意精度の有理数
+ *
+ * 常に既約分数で、分母は正の数で保持する。
+ * リテラルは整数のほか `1/3` のような分数と `0.25` のような小数を受け付ける。
+ */
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+impl Rational {
+    // 分子と分母から生成し、約分する。分母が 0 の場合は None
+    pub fn new(num: BigInt, den: BigInt) -> Option<Self> {
+        if den.is_zero() {
+            return None;
+        }
+        let g = num.gcd(&den);
+        let (mut num, _) = num.div_rem(&g).unwrap();
+        let (mut den, _) = den.div_rem(&g).unwrap();
+        if den.is_negative() {
+            num = -&num;
+            den = -&den;
+        }
+        Some(Self { num, den })
+    }
+
+    // 分子
+    pub fn numer(&self) -> &BigInt {
+        &self.num
+    }
+
+    // 分母
+    pub fn denom(&self) -> &BigInt {
+        &self.den
+    }
+
+    // 整数かどうか
+    pub fn is_integer(&self) -> bool {
+        self.den == BigInt::one()
+    }
+
+    // 0 方向に切り捨てた整数部分
+    pub fn trunc(&self) -> BigInt {
+        self.num.div_rem(&self.den).unwrap().0
+    }
+
+    // 負の無限大方向に丸めた整数
+    pub fn floor(&self) -> BigInt {
+        let t = self.trunc();
+        if self.num.is_negative() && !self.is_integer() {
+            &t - &BigInt::one()
+        } else {
+            t
+        }
+    }
+
+    // 正の無限大方向に丸めた整数
+    pub fn ceil(&self) -> BigInt {
+        let t = self.trunc();
+        if !self.num.is_negative() && !self.is_integer() {
+            &t + &BigInt::one()
+        } else {
+            t
+        }
+    }
+
+    // 四捨五入 (0 から遠い方へ) した整数
+    pub fn round(&self) -> BigInt {
+        // |x| + 1/2 を切り捨ててから符号を戻す
+        let twice = &(&self.num.abs() + &self.num.abs()) + &self.den;
+        let (q, _) = twice.div_rem(&(&self.den + &self.den)).unwrap();
+        if self.num.is_negative() {
+            -&q
+        } else {
+            q
+        }
+    }
+
+    // 小数点以下 `places` 桁に四捨五入した小数表記
+    pub fn to_decimal_string(&self, places: usize) -> String {
+        // 10^places 倍して 0 から遠い方へ丸めた整数を求める
+        let scale = BigInt::from(10i64).pow(places as u32);
+        let (q, r) = (&self.num.abs() * &scale).div_rem(&self.den).unwrap();
+        let twice = &r + &r;
+        let q = if twice >= self.den {
+            &q + &BigInt::one()
+        } else {
+            q
+        };
+
+        let digits = format!("{:0>width$}", q.to_string(), width = places + 1);
+        let (int, frac) = digits.split_at(digits.len() - places);
+        let sign = if self.num.is_negative() && !q.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        if places == 0 {
+            format!("{}{}", sign, int)
+        } else {
+            format!("{}{}.{}", sign, int, frac)
+        }
+    }
+
+    // 帯分数表記 (例: `-1 1/2`)
+    pub fn to_mixed_string(&self) -> String {
+        let int = self.trunc();
+        if self.is_integer() || int.is_zero() {
+            return self.to_string();
+        }
+        let frac = &self.num.abs() - &(&int.abs() * &self.den);
+        format!("{} {}/{}", int, frac, self.den)
+    }
+
+    // 小数のリテラルを解釈する
+    fn parse_decimal(token: &str) -> Option<Self> {
+        let (int, frac) = token.split_once('.')?;
+        if frac.is_empty() || !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let num = BigInt::parse(&format!("{}{}", int, frac))?;
+        let den = BigInt::from(10i64).pow(frac.len() as u32);
+        Self::new(num, den)
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(num: BigInt) -> Self {
+        Self {
+            num,
+            den: BigInt::one(),
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 分母は正なので、分母を払って分子同士を比べる
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl fmt::Debug for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Number for Rational {
+    const KIND: &'static str = "rational";
+
+    fn parse(token: &str, _config: &Config) -> Option<Self> {
+        if let Some((num, den)) = token.split_once('/') {
+            // 分母に符号は付けられない
+            if den.starts_with(['+', '-']) {
+                return None;
+            }
+            return Self::new(BigInt::parse(num)?, BigInt::parse(den)?);
+        }
+        if token.contains('.') {
+            return Self::parse_decimal(token);
+        }
+        BigInt::parse(token).map(Self::from)
+    }
+
+    fn from_i64(n: i64, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::from(BigInt::from(n)))
+    }
+
+    fn from_f64(x: f64, config: &Config) -> Result<Self, ArithError> {
+        // 最短の 10 進表記を正確に分数にする
+        if !x.is_finite() {
+            return Err(ArithError::Domain);
+        }
+        Self::parse(&x.to_string(), config).ok_or(ArithError::Domain)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if !self.is_integer() {
+            return None;
+        }
+        self.num.to_i128()?.try_into().ok()
+    }
+
+    fn to_bigint(&self) -> Option<BigInt> {
+        self.is_integer().then(|| self.num.clone())
+    }
+
+    fn from_bigint(n: &BigInt, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::from(n.clone()))
+    }
+
+    fn compare(&self, rhs: &Self) -> Result<Ordering, ArithError> {
+        Ok(self.cmp(rhs))
+    }
+
+    fn add(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        let num = &(&self.num * &rhs.den) + &(&rhs.num * &self.den);
+        Ok(Self::new(num, &self.den * &rhs.den).unwrap())
+    }
+
+    fn sub(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        let num = &(&self.num * &rhs.den) - &(&rhs.num * &self.den);
+        Ok(Self::new(num, &self.den * &rhs.den).unwrap())
+    }
+
+    fn mul(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Ok(Self::new(&self.num * &rhs.num, &self.den * &rhs.den).unwrap())
+    }
+
+    fn div(&self, rhs: &Self, _config: &Config) -> Result<Self, ArithError> {
+        Self::new(&self.num * &rhs.den, &self.den * &rhs.num).ok_or(ArithError::DivisionByZero)
+    }
+
+    fn rem(&self, rhs: &Self, config: &Config) -> Result<Self, ArithError> {
+        // 商を 0 方向に切り捨てた余り (x - y * trunc(x / y))
+        let q = Self::from(self.div(rhs, config)?.trunc());
+        self.sub(&rhs.mul(&q, config)?, config)
+    }
+
+    fn pow(&self, exp: &Self, config: &Config) -> Result<Self, ArithError> {
+        // 負の整数の指数は逆数のべき乗にする
+        let exp = exp.to_i64().ok_or(ArithError::Domain)?;
+        let bits = self.num.bits().max(self.den.bits()).saturating_sub(1);
+        check_pow_size(bits as f64, exp.unsigned_abs())?;
+        let p = pow_by_squaring(self, exp.unsigned_abs(), config)?;
+        if exp < 0 {
+            Self::from(BigInt::one()).div(&p, config)
+        } else {
+            Ok(p)
+        }
+    }
+
+    fn call(&self, func: Function, _config: &Config) -> Result<Self, ArithError> {
+        match func {
+            Function::Neg => Ok(Self {
+                num: -&self.num,
+                den: self.den.clone(),
+            }),
+            Function::Abs => Ok(Self {
+                num: self.num.abs(),
+                den: self.den.clone(),
+            }),
+            Function::Floor => Ok(Self::from(self.floor())),
+            Function::Ceil => Ok(Self::from(self.ceil())),
+            Function::Round => Ok(Self::from(self.round())),
+            Function::Trunc => Ok(Self::from(self.trunc())),
+            _ => Err(ArithError::Unsupported),
+        }
+    }
+
+    fn format(&self, config: &Config) -> String {
+        match config.rational_format {
+            RationalFormat::Fraction => self.to_string(),
+            RationalFormat::Mixed => self.to_mixed_string(),
+            RationalFormat::Decimal => {
+                self.to_decimal_string(config.clamped_precision().unwrap_or(DEFAULT_DECIMAL_PLACES))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rat(s: &str) -> Rational {
+        Rational::parse(s, &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(rat("2/4").to_string(), "1/2");
+        assert_eq!(rat("-6/3").to_string(), "-2");
+        assert_eq!(rat("0.25").to_string(), "1/4");
+        assert_eq!(rat("-1.50").to_string(), "-3/2");
+        assert!(Rational::parse("1/0", &Config::default()).is_none());
+        assert!(Rational::parse("1/-2", &Config::default()).is_none());
+        assert!(Rational::parse("1.", &Config::default()).is_none());
+    }
+
+    #[test]
+    fn test_arith() {
+        let config = Config::default();
+        assert_eq!(rat("1/3").add(&rat("1/6"), &config).unwrap(), rat("1/2"));
+        assert_eq!(rat("1/3").sub(&rat("1/2"), &config).unwrap(), rat("-1/6"));
+        assert_eq!(rat("2/3").mul(&rat("3/4"), &config).unwrap(), rat("1/2"));
+        assert_eq!(rat("7/2").rem(&rat("1"), &config).unwrap(), rat("1/2"));
+        assert_eq!(rat("-7/2").rem(&rat("1"), &config).unwrap(), rat("-1/2"));
+        assert_eq!(
+            rat("1").div(&rat("0"), &config),
+            Err(ArithError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(rat("-3/2").to_mixed_string(), "-1 1/2");
+        assert_eq!(rat("1/2").to_mixed_string(), "1/2");
+        assert_eq!(rat("2/3").to_decimal_string(4), "0.6667");
+        assert_eq!(rat("-1/3").to_decimal_string(2), "-0.33");
+        assert_eq!(rat("-1/300").to_decimal_string(2), "0.00");
+        assert_eq!(rat("5/2").to_decimal_string(0), "3");
+    }
+}
@@ -0,0 +1,162 @@
+use super::BigInt;
+use crate::config::{Config, Radix};
+
+// リテラルに使える基数。接頭辞で区別する
+const LITERAL_RADIXES: [Radix; 3] = [Radix::Hex, Radix::Oct, Radix::Bin];
+
+/**
+ * ビット列を設定のワード長に切り詰め、符号付きの場合は最上位ビットを符号として解釈する
+ */
+pub(crate) fn from_bits(bits: u128, config: &Config) -> i128 {
+    let width = config.word_size.bits();
+    let bits = bits & config.word_size.mask();
+    if config.signed && bits >> (width - 1) & 1 == 1 {
+        bits as i128 - (1 << width)
+    } else {
+        bits as i128
+    }
+}
+
+/**
+ * 整数を 2 の補数で表したビット列を設定のワード長に切り詰める
+ */
+pub(crate) fn to_bits(n: i128, config: &Config) -> u128 {
+    n as u128 & config.word_size.mask()
+}
+
+/**
+ * `0x`, `0o`, `0b` で始まる 16 進数、8 進数、2 進数のリテラルを解釈する
+ *
+ * 先頭に `-` を付けられ、数字の間は `_` で区切れる。
+ * ワード長を超える場合は None
+ */
+pub(crate) fn parse_literal(token: &str, config: &Config) -> Option<i128> {
+    let (neg, radix, digits) = split_literal(token)?;
+    let bits = u128::from_str_radix(&digits, radix.base()).ok()?;
+    if bits > config.word_size.mask() {
+        return None;
+    }
+    let n = from_bits(bits, config);
+    if neg {
+        n.checked_neg()
+    } else {
+        Some(n)
+    }
+}
+
+/**
+ * 値の大きさに関わらず、書式として正しい基数付きのリテラルかどうか
+ */
+pub(crate) fn is_literal(token: &str) -> bool {
+    split_literal(token).is_some()
+}
+
+// リテラルを符号、基数、`_` を除いた数字に分ける
+fn split_literal(token: &str) -> Option<(bool, Radix, String)> {
+    let (neg, body) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let prefix = body.get(..2)?;
+    let radix = LITERAL_RADIXES
+        .into_iter()
+        .find(|r| prefix.eq_ignore_ascii_case(r.prefix()))?;
+    let digits = body[2..].replace('_', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix.base())) {
+        return None;
+    }
+    Some((neg, radix, digits))
+}
+
+/**
+ * 整数を設定の基数で整形する。複数の基数は空白で区切って並べる
+ *
+ * 10 進数以外では、ワードに収まる負の数を 2 の補数のビット列で表す
+ */
+pub(crate) fn format_int(n: &BigInt, config: &Config) -> String {
+    if config.radix.is_empty() {
+        return n.to_string();
+    }
+    config
+        .radix
+        .iter()
+        .map(|radix| match radix {
+            Radix::Dec => n.to_string(),
+            _ => {
+                let min = -(1i128 << (config.word_size.bits() - 1));
+                match n.to_i128() {
+                    Some(v) if (min..0).contains(&v) => {
+                        let bits = BigInt::from(to_bits(v, config) as i128);
+                        format!("{}{}", radix.prefix(), bits.to_str_radix(radix.base()))
+                    }
+                    _ => {
+                        let sign = if n.is_negative() { "-" } else { "" };
+                        format!("{}{}{}", sign, radix.prefix(), n.to_str_radix(radix.base()))
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WordSize;
+
+    fn config(word_size: WordSize, signed: bool) -> Config {
+        Config {
+            word_size,
+            signed,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_literal() {
+        let c = Config::default();
+        assert_eq!(parse_literal("0xFF", &c), Some(255));
+        assert_eq!(parse_literal("0Xff", &c), Some(255));
+        assert_eq!(parse_literal("0b1010", &c), Some(10));
+        assert_eq!(parse_literal("0o17", &c), Some(15));
+        assert_eq!(parse_literal("-0x10", &c), Some(-16));
+        assert_eq!(parse_literal("0xFFFF_FFFF", &c), Some(0xFFFF_FFFF));
+        assert_eq!(parse_literal("0xFFFFFFFFFFFFFFFF", &c), Some(-1));
+        assert_eq!(parse_literal("0x1FFFFFFFFFFFFFFFF", &c), None);
+        assert_eq!(parse_literal("0x", &c), None);
+        assert_eq!(parse_literal("0b102", &c), None);
+        assert_eq!(parse_literal("0x+1", &c), None);
+        assert_eq!(parse_literal("12", &c), None);
+
+        assert_eq!(parse_literal("0xFF", &config(WordSize::W8, true)), Some(-1));
+        assert_eq!(
+            parse_literal("0xFF", &config(WordSize::W8, false)),
+            Some(255)
+        );
+        assert_eq!(parse_literal("0x100", &config(WordSize::W8, false)), None);
+
+        // 範囲外でも書式が正しければリテラルとみなす
+        assert!(is_literal("0x1FFFFFFFFFFFFFFFF"));
+        assert!(is_literal("-0b1_0"));
+        assert!(!is_literal("0b102"));
+        assert!(!is_literal("0x"));
+    }
+
+    #[test]
+    fn test_format_int() {
+        let mut c = config(WordSize::W8, true);
+        c.radix = vec![Radix::Dec, Radix::Hex, Radix::Oct, Radix::Bin];
+        assert_eq!(format_int(&BigInt::from(10i64), &c), "10 0xA 0o12 0b1010");
+        assert_eq!(
+            format_int(&BigInt::from(-1i64), &c),
+            "-1 0xFF 0o377 0b11111111"
+        );
+        assert_eq!(
+            format_int(&BigInt::from(-300i64), &c),
+            "-300 -0x12C -0o454 -0b100101100"
+        );
+        c.radix = vec![];
+        assert_eq!(format_int(&BigInt::from(-1i64), &c), "-1");
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Arity, Op};
+use crate::error::ArithError;
+use crate::number::{Function, Number};
+
+/**
+ * 算術演算子
+ */
+pub(super) fn ops<N: Number>() -> Vec<Op<N>> {
+    vec![
+        Op::new(
+            "+",
+            Arity::Fixed(2),
+            "( x y -- x+y ) addition",
+            |args, config| binary(args, |x, y| x.add(y, config)),
+        ),
+        Op::new(
+            "-",
+            Arity::Fixed(2),
+            "( x y -- x-y ) subtraction",
+            |args, config| binary(args, |x, y| x.sub(y, config)),
+        ),
+        Op::new(
+            "*",
+            Arity::Fixed(2),
+            "( x y -- x*y ) multiplication",
+            |args, config| binary(args, |x, y| x.mul(y, config)),
+        ),
+        Op::new(
+            "/",
+            Arity::Fixed(2),
+            "( x y -- x/y ) division",
+            |args, config| binary(args, |x, y| x.div(y, config)),
+        ),
+        Op::new(
+            "%",
+            Arity::Fixed(2),
+            "( x y -- x%y ) remainder",
+            |args, config| binary(args, |x, y| x.rem(y, config)),
+        ),
+        Op::new(
+            "cplx",
+            Arity::Fixed(2),
+            "( re im -- z ) complex number from real and imaginary parts",
+            |args, config| binary(args, |x, y| x.cplx(y, config)),
+        ),
+        Op::new(
+            "neg",
+            Arity::Fixed(1),
+            "( x -- -x ) negation",
+            |args, config| unary(args, |x| x.call(Function::Neg, config)),
+        ),
+        Op::new(
+            "abs",
+            Arity::Fixed(1),
+            "( x -- |x| ) absolute value",
+            |args, config| unary(args, |x| x.call(Function::Abs, config)),
+        ),
+        Op::new(
+            "sqrt",
+            Arity::Fixed(1),
+            "( x -- sqrt(x) ) square root",
+            |args, config| unary(args, |x| x.call(Function::Sqrt, config)),
+        ),
+        Op::new(
+            "conj",
+            Arity::Fixed(1),
+            "( z -- conj(z) ) complex conjugate",
+            |args, config| unary(args, |x| x.call(Function::Conj, config)),
+        ),
+        Op::new(
+            "arg",
+            Arity::Fixed(1),
+            "( z -- arg(z) ) argument (angle) of a complex number",
+            |args, config| unary(args, |x| x.call(Function::Arg, config)),
+        ),
+        Op::new(
+            "re",
+            Arity::Fixed(1),
+            "( z -- re(z) ) real part",
+            |args, config| unary(args, |x| x.call(Function::Re, config)),
+        ),
+        Op::new(
+            "im",
+            Arity::Fixed(1),
+            "( z -- im(z) ) imaginary part",
+            |args, config| unary(args, |x| x.call(Function::Im, config)),
+        ),
+        Op::new(
+            "sum",
+            Arity::Variadic(1),
+            "( x1 ... xn -- x1+...+xn ) sum of the whole stack",
+            |args, config| fold(args, |x, y| x.add(y, config)),
+        ),
+        Op::new(
+            "prod",
+            Arity::Variadic(1),
+            "( x1 ... xn -- x1*...*xn ) product of the whole stack",
+            |args, config| fold(args, |x, y| x.mul(y, config)),
+        ),
+    ]
+}
+
+/**
+ * 値を一つ取る演算子を適用する
+ */
+pub(super) fn unary<N: Number>(
+    mut args: Vec<N>,
+    f: impl FnOnce(&N) -> Result<N, ArithError>,
+) -> Result<Vec<N>, ArithError> {
+    let x = args.pop().unwrap();
+    Ok(vec![f(&x)?])
+}
+
+/**
+ * 値を二つ取る演算子を適用する
+ */
+pub(super) fn binary<N: Number>(
+    mut args: Vec<N>,
+    f: impl FnOnce(&N, &N) -> Result<N, ArithError>,
+) -> Result<Vec<N>, ArithError> {
+    let y = args.pop().unwrap();
+    let x = args.pop().unwrap();
+    Ok(vec![f(&x, &y)?])
+}
+
+/**
+ * スタック上の全ての値を左から順に畳み込む
+ */
+pub(super) fn fold<N: Number>(
+    args: Vec<N>,
+    f: impl Fn(&N, &N) -> Result<N, ArithError>,
+) -> Result<Vec<N>, ArithError> {
+    let mut args = args.into_iter();
+    let first = args.next().unwrap();
+    Ok(vec![args.try_fold(first, |acc, x| f(&acc, &x))?])
+}
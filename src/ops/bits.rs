@@ -0,0 +1,220 @@
+use super::arith::{binary, unary};
+use super::{Arity, Op};
+use crate::config::Config;
+use crate::error::ArithError;
+use crate::number::{from_bits, to_bits, BigInt, Number};
+
+/**
+ * ビット演算子
+ *
+ * 値は設定のワード長の 2 の補数のビット列として扱い、結果は符号付きか符号なしかの設定に従って解釈する。
+ * ワード長が効くのはこれらの演算子だけで、`+` などの算術演算子の結果はワード長に切り詰めない。
+ * `and`, `or`, `not` は真偽値の演算子なので、ビット演算は `b` を付けた名前にする
+ */
+pub(super) fn ops<N: Number>() -> Vec<Op<N>> {
+    vec![
+        Op::new(
+            "band",
+            Arity::Fixed(2),
+            "( a b -- a&b ) bitwise and",
+            |args, config| bitwise(args, config, |a, b| a & b),
+        ),
+        Op::new(
+            "bor",
+            Arity::Fixed(2),
+            "( a b -- a|b ) bitwise or",
+            |args, config| bitwise(args, config, |a, b| a | b),
+        ),
+        Op::new(
+            "bxor",
+            Arity::Fixed(2),
+            "( a b -- a^b ) bitwise exclusive or (alias `xor`)",
+            |args, config| bitwise(args, config, |a, b| a ^ b),
+        ),
+        Op::new(
+            "bnot",
+            Arity::Fixed(1),
+            "( a -- ~a ) bitwise complement",
+            |args, config| unary(args, |a| from_word(!word(a, config)?, config)),
+        ),
+        Op::new(
+            "shl",
+            Arity::Fixed(2),
+            "( a n -- a<<n ) shift left by n bits",
+            |args, config| {
+                binary(args, |a, n| {
+                    let (a, n) = (word(a, config)?, shift(n)?);
+                    from_word(a.checked_shl(n).unwrap_or(0), config)
+                })
+            },
+        ),
+        Op::new(
+            "shr",
+            Arity::Fixed(2),
+            "( a n -- a>>n ) shift right by n bits (arithmetic when signed)",
+            |args, config| {
+                binary(args, |a, n| {
+                    let (a, n) = (word(a, config)?, shift(n)?);
+                    // 符号付きの場合は符号ビットを保ったまま右に詰める
+                    let shifted = from_bits(a, config) >> n.min(127);
+                    from_word(to_bits(shifted, config), config)
+                })
+            },
+        ),
+        Op::new(
+            "rotl",
+            Arity::Fixed(2),
+            "( a n -- a' ) rotate left by n bits within the word",
+            |args, config| {
+                binary(args, |a, n| {
+                    let (a, n) = (word(a, config)?, shift(n)?);
+                    from_word(rotate(a, n, config), config)
+                })
+            },
+        ),
+        Op::new(
+            "rotr",
+            Arity::Fixed(2),
+            "( a n -- a' ) rotate right by n bits within the word",
+            |args, config| {
+                binary(args, |a, n| {
+                    let (a, n) = (word(a, config)?, shift(n)?);
+                    let width = config.word_size.bits();
+                    from_word(rotate(a, width - n % width, config), config)
+                })
+            },
+        ),
+        Op::new(
+            "popcnt",
+            Arity::Fixed(1),
+            "( a -- n ) number of set bits",
+            |args, config| {
+                unary(args, |a| {
+                    N::from_i64(word(a, config)?.count_ones() as i64, config)
+                })
+            },
+        ),
+    ]
+}
+
+/**
+ * 整数値をワード長のビット列に変換する
+ */
+fn word<N: Number>(x: &N, config: &Config) -> Result<u128, ArithError> {
+    let n = x.to_bigint().ok_or(ArithError::Domain)?;
+    let n = n.to_i128().ok_or(ArithError::Overflow)?;
+    Ok(to_bits(n, config))
+}
+
+/**
+ * ビット列を設定に従って解釈した値に変換する
+ */
+fn from_word<N: Number>(bits: u128, config: &Config) -> Result<N, ArithError> {
+    N::from_bigint(&BigInt::from(from_bits(bits, config)), config)
+}
+
+/**
+ * 2 つの値のビット列に演算を適用する
+ */
+fn bitwise<N: Number>(
+    args: Vec<N>,
+    config: &Config,
+    f: impl Fn(u128, u128) -> u128,
+) -> Result<Vec<N>, ArithError> {
+    binary(args, |a, b| {
+        from_word(f(word(a, config)?, word(b, config)?), config)
+    })
+}
+
+/**
+ * シフト量を表す 0 以上の整数に変換する
+ */
+fn shift<N: Number>(n: &N) -> Result<u32, ArithError> {
+    n.to_i64()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or(ArithError::Domain)
+}
+
+/**
+ * ワード内で左に回転する
+ */
+fn rotate(bits: u128, n: u32, config: &Config) -> u128 {
+    let width = config.word_size.bits();
+    let n = n % width;
+    if n == 0 {
+        return bits;
+    }
+    ((bits << n) | (bits >> (width - n))) & config.word_size.mask()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, Radix, WordSize};
+    use crate::{BigInt, Number, RpnCalculator};
+
+    fn calc(word_size: WordSize, signed: bool) -> RpnCalculator<i64> {
+        RpnCalculator::new(Config {
+            word_size,
+            signed,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let mut c = calc(WordSize::W64, true);
+        assert_eq!(c.eval("0xF0 0x3C band").unwrap(), 0x30);
+        assert_eq!(c.eval("0xF0 0x0F bor").unwrap(), 0xFF);
+        assert_eq!(c.eval("0b1100 0b1010 bxor").unwrap(), 0b0110);
+        assert_eq!(c.eval("0 bnot").unwrap(), -1);
+        assert_eq!(c.eval("0b1100 0b1010 xor").unwrap(), 0b0110);
+        assert_eq!(c.eval("1 4 shl").unwrap(), 16);
+        assert_eq!(c.eval("1 64 shl").unwrap(), 0);
+        assert_eq!(c.eval("-16 2 shr").unwrap(), -4);
+        assert_eq!(c.eval("0xFF popcnt").unwrap(), 8);
+        assert_eq!(c.eval("-1 popcnt").unwrap(), 64);
+        assert!(c.eval("1 -1 shl").is_err());
+    }
+
+    #[test]
+    fn test_word_size() {
+        let mut c = calc(WordSize::W8, false);
+        assert_eq!(c.eval("0 bnot").unwrap(), 255);
+        assert_eq!(c.eval("0x81 1 rotl").unwrap(), 0x03);
+        assert_eq!(c.eval("0x81 1 rotr").unwrap(), 0xC0);
+        assert_eq!(c.eval("0x81 9 rotl").unwrap(), 0x03);
+        assert_eq!(c.eval("0x80 1 shr").unwrap(), 0x40);
+        assert_eq!(c.eval("0xFF 4 shl").unwrap(), 0xF0);
+        assert!(c.eval("0x100").is_err());
+
+        let mut c = calc(WordSize::W8, true);
+        assert_eq!(c.eval("0xFF").unwrap(), -1);
+        assert_eq!(c.eval("0x80 1 shr").unwrap(), -64);
+        assert_eq!(c.eval("127 1 shl").unwrap(), -2);
+
+        let mut c = calc(WordSize::W16, false);
+        assert_eq!(c.eval("0x1234 8 rotl").unwrap(), 0x3412);
+    }
+
+    #[test]
+    fn test_radix_output() {
+        let config = Config {
+            word_size: WordSize::W16,
+            radix: vec![Radix::Hex, Radix::Bin],
+            ..Config::default()
+        };
+        let mut c = RpnCalculator::<BigInt>::new(config.clone());
+        assert_eq!(
+            c.eval("0b1010 0xF0 bor").unwrap().format(&config),
+            "0xFA 0b11111010"
+        );
+        assert_eq!(
+            c.eval("0 1 -").unwrap().format(&config),
+            "0xFFFF 0b1111111111111111"
+        );
+        assert_eq!((-300i32).format(&config), "0xFED4 0b1111111011010100");
+        assert!(RpnCalculator::<f64>::new(config)
+            .eval("1.5 1 band")
+            .is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::logic::truthy;
+use super::{Arity, ValueOp};
+use crate::calculator::Context;
+use crate::error::ArithError;
+use crate::number::Number;
+use crate::value::{Quotation, Value};
+
+/**
+ * クォーテーションを評価するコンビネータ
+ *
+ * クォーテーションは残りのスタックに対して評価する
+ */
+pub(super) fn ops<N: Number>() -> Vec<ValueOp<N>> {
+    vec![
+        ValueOp::new(
+            "if",
+            Arity::Fixed(2),
+            "( cond [then] -- ... ) evaluate then if cond is true",
+            |mut args, ctx| {
+                let then = args.pop().unwrap().into_quote()?;
+                if condition(args.pop().unwrap(), ctx)? {
+                    ctx.call(&then)?;
+                }
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "ifelse",
+            Arity::Fixed(3),
+            "( cond [then] [else] -- ... ) evaluate then if cond is true, otherwise else",
+            |mut args, ctx| {
+                let otherwise = args.pop().unwrap().into_quote()?;
+                let then = args.pop().unwrap().into_quote()?;
+                if condition(args.pop().unwrap(), ctx)? {
+                    ctx.call(&then)?;
+                } else {
+                    ctx.call(&otherwise)?;
+                }
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "times",
+            Arity::Fixed(2),
+            "( n [body] -- ... ) evaluate body n times",
+            |mut args, ctx| {
+                let body = args.pop().unwrap().into_quote()?;
+                let n = args.pop().unwrap().into_num()?;
+                let n = n
+                    .to_i64()
+                    .and_then(|n| u64::try_from(n).ok())
+                    .ok_or(ArithError::Domain)?;
+                for _ in 0..n {
+                    ctx.call(&body)?;
+                }
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "while",
+            Arity::Fixed(2),
+            "( [cond] [body] -- ... ) evaluate body while cond leaves a true value",
+            |mut args, ctx| {
+                let body = args.pop().unwrap().into_quote()?;
+                let cond = args.pop().unwrap().into_quote()?;
+                loop {
+                    ctx.call(&cond)?;
+                    let flag = ctx.stack().pop().ok_or(ArithError::StackUnderflow)?;
+                    if !condition(flag, ctx)? {
+                        break;
+                    }
+                    ctx.call(&body)?;
+                }
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "each",
+            Arity::Fixed(2),
+            "( [list] [f] -- ... ) evaluate f on each element",
+            |mut args, ctx| {
+                let f = args.pop().unwrap().into_quote()?;
+                let list = args.pop().unwrap().into_quote()?;
+                for x in list.values()? {
+                    ctx.stack().push(x);
+                    ctx.call(&f)?;
+                }
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "map",
+            Arity::Fixed(2),
+            "( [list] [f] -- [list'] ) collect what f leaves for each element",
+            |mut args, ctx| {
+                let f = args.pop().unwrap().into_quote()?;
+                let list = args.pop().unwrap().into_quote()?;
+                let mut res = Vec::new();
+                for x in list.values()? {
+                    res.extend(apply_to(x, &f, ctx)?);
+                }
+                Ok(vec![Value::Quote(Quotation::from_values(res)?)])
+            },
+        ),
+        ValueOp::new(
+            "fold",
+            Arity::Fixed(3),
+            "( [list] init [f] -- acc ) combine the elements with f starting from init",
+            |mut args, ctx| {
+                let f = args.pop().unwrap().into_quote()?;
+                let init = args.pop().unwrap();
+                let list = args.pop().unwrap().into_quote()?;
+                ctx.stack().push(init);
+                for x in list.values()? {
+                    ctx.stack().push(x);
+                    ctx.call(&f)?;
+                }
+                Ok(vec![])
+            },
+        ),
+    ]
+}
+
+/**
+ * 条件の値を真偽値にする
+ */
+fn condition<N: Number>(cond: Value<N>, ctx: &Context<N>) -> Result<bool, ArithError> {
+    truthy(&cond.into_num()?, ctx.config())
+}
+
+/**
+ * 値を一つ積んでクォーテーションを評価し、その上に残った値を取り出す
+ *
+ * 元のスタックの値を消費した場合はスタック不足のエラーにする
+ */
+fn apply_to<N: Number>(
+    x: Value<N>,
+    f: &Quotation<N>,
+    ctx: &mut Context<N>,
+) -> Result<Vec<Value<N>>, ArithError> {
+    let base = ctx.stack().len();
+    ctx.stack().push(x);
+    ctx.call(f)?;
+    if ctx.stack().len() < base {
+        return Err(ArithError::StackUnderflow);
+    }
+    Ok(ctx.stack().split_off(base))
+}
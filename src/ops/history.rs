@@ -0,0 +1,36 @@
+use super::{Arity, ValueOp};
+use crate::number::Number;
+
+/**
+ * セッションのスタックの履歴を扱うワード
+ *
+ * 取り消し・やり直しは行単位で、セッションでない評価では履歴を持たない
+ */
+pub(super) fn ops<N: Number>() -> Vec<ValueOp<N>> {
+    vec![
+        ValueOp::new(
+            "undo",
+            Arity::Fixed(0),
+            "( -- ) restore the stack before the previous line",
+            |_, ctx| {
+                ctx.undo()?;
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "redo",
+            Arity::Fixed(0),
+            "( -- ) restore the stack changed by the last undo",
+            |_, ctx| {
+                ctx.redo()?;
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "lastx",
+            Arity::Fixed(0),
+            "( -- x ) push the top value taken by the last operator",
+            |_, ctx| Ok(vec![ctx.last_x()?.clone()]),
+        ),
+    ]
+}
@@ -0,0 +1,373 @@
+use super::arith::{binary, unary};
+use super::{Arity, Op};
+use crate::config::Config;
+use crate::error::ArithError;
+use crate::number::{BigInt, Number, MAX_RESULT_BITS};
+
+// Miller-Rabin 法で使う底。3.3 * 10^24 未満の数では判定が確定する
+const WITNESSES: [i64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/**
+ * べき乗と整数論の演算子
+ *
+ * 整数論の演算子は整数値にのみ適用でき、小数部を持つ値は定義域外のエラーにする
+ */
+pub(super) fn ops<N: Number>() -> Vec<Op<N>> {
+    vec![
+        Op::new(
+            "^",
+            Arity::Fixed(2),
+            "( x y -- x^y ) power",
+            |args, config| binary(args, |x, y| x.pow(y, config)),
+        ),
+        Op::new(
+            "pow",
+            Arity::Fixed(2),
+            "( x y -- x^y ) power",
+            |args, config| binary(args, |x, y| x.pow(y, config)),
+        ),
+        Op::new(
+            "isqrt",
+            Arity::Fixed(1),
+            "( n -- floor(sqrt(n)) ) integer square root",
+            |args, config| {
+                unary(args, |n| {
+                    let root = integer(n)?.isqrt().ok_or(ArithError::Domain)?;
+                    N::from_bigint(&root, config)
+                })
+            },
+        ),
+        Op::new(
+            "gcd",
+            Arity::Fixed(2),
+            "( a b -- gcd(a,b) ) greatest common divisor",
+            |args, config| {
+                binary(args, |a, b| {
+                    N::from_bigint(&integer(a)?.gcd(&integer(b)?), config)
+                })
+            },
+        ),
+        Op::new(
+            "lcm",
+            Arity::Fixed(2),
+            "( a b -- lcm(a,b) ) least common multiple",
+            |args, config| {
+                binary(args, |a, b| {
+                    N::from_bigint(&lcm(&integer(a)?, &integer(b)?), config)
+                })
+            },
+        ),
+        Op::new(
+            "fact",
+            Arity::Fixed(1),
+            "( n -- n! ) factorial",
+            |args, config| {
+                unary(args, |n| {
+                    let n = count(n)?;
+                    falling_factorial(n, n, config)
+                })
+            },
+        ),
+        Op::new(
+            "ncr",
+            Arity::Fixed(2),
+            "( n r -- nCr ) number of combinations",
+            |args, config| {
+                binary(args, |n, r| {
+                    let (n, r) = (count(n)?, count(r)?);
+                    if r > n {
+                        return Err(ArithError::Domain);
+                    }
+                    binomial(n, r, config)
+                })
+            },
+        ),
+        Op::new(
+            "npr",
+            Arity::Fixed(2),
+            "( n r -- nPr ) number of permutations",
+            |args, config| {
+                binary(args, |n, r| {
+                    let (n, r) = (count(n)?, count(r)?);
+                    if r > n {
+                        return Err(ArithError::Domain);
+                    }
+                    falling_factorial(n, r, config)
+                })
+            },
+        ),
+        Op::new(
+            "modpow",
+            Arity::Fixed(3),
+            "( b e m -- b^e mod m ) modular exponentiation",
+            |args, config| {
+                let [b, e, m] = integers::<3, N>(&args)?;
+                if e.is_negative() {
+                    return Err(ArithError::Domain);
+                }
+                Ok(vec![N::from_bigint(&b.modpow(&e, &modulus(m)?), config)?])
+            },
+        ),
+        Op::new(
+            "modinv",
+            Arity::Fixed(2),
+            "( a m -- a^-1 mod m ) modular multiplicative inverse",
+            |args, config| {
+                let [a, m] = integers::<2, N>(&args)?;
+                let inv = a.modinv(&modulus(m)?).ok_or(ArithError::Domain)?;
+                Ok(vec![N::from_bigint(&inv, config)?])
+            },
+        ),
+        Op::new(
+            "isprime",
+            Arity::Fixed(1),
+            "( n -- flag ) 1 if n is prime, otherwise 0",
+            |args, config| unary(args, |n| N::from_i64(is_prime(&integer(n)?) as i64, config)),
+        ),
+    ]
+}
+
+/**
+ * 整数値を任意精度整数に変換する。整数でない場合は定義域外
+ */
+fn integer<N: Number>(x: &N) -> Result<BigInt, ArithError> {
+    x.to_bigint().ok_or(ArithError::Domain)
+}
+
+/**
+ * 全ての引数を任意精度整数に変換する
+ */
+fn integers<const K: usize, N: Number>(args: &[N]) -> Result<[BigInt; K], ArithError> {
+    let ints = args.iter().map(integer).collect::<Result<Vec<_>, _>>()?;
+    Ok(ints.try_into().unwrap())
+}
+
+/**
+ * 個数を表す 0 以上の整数に変換する
+ */
+fn count<N: Number>(x: &N) -> Result<u64, ArithError> {
+    x.to_i64()
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or(ArithError::Domain)
+}
+
+/**
+ * 法として使える正の整数か確かめる
+ */
+fn modulus(m: BigInt) -> Result<BigInt, ArithError> {
+    if m.is_zero() {
+        Err(ArithError::DivisionByZero)
+    } else if m.is_negative() {
+        Err(ArithError::Domain)
+    } else {
+        Ok(m)
+    }
+}
+
+/**
+ * 最小公倍数 (常に 0 以上)
+ */
+fn lcm(a: &BigInt, b: &BigInt) -> BigInt {
+    if a.is_zero() || b.is_zero() {
+        return BigInt::zero();
+    }
+    let (q, _) = a.abs().div_rem(&a.gcd(b)).unwrap();
+    &q * &b.abs()
+}
+
+/**
+ * n * (n - 1) * ... * (n - r + 1)
+ *
+ * 数値型の乗算で求めるので、固定長の整数型では結果が収まらなくなった時点で、
+ * 浮動小数点数では無限大になった時点でエラーになる
+ */
+fn falling_factorial<N: Number>(n: u64, r: u64, config: &Config) -> Result<N, ArithError> {
+    check_size(log2_factorial(n) - log2_factorial(n - r))?;
+    let mut acc = N::from_i64(1, config)?;
+    for k in (n - r + 1)..=n {
+        let k = i64::try_from(k).map_err(|_| ArithError::Overflow)?;
+        acc = finite(acc.mul(&N::from_i64(k, config)?, config)?)?;
+    }
+    Ok(acc)
+}
+
+/**
+ * 二項係数 nCr
+ *
+ * 途中の値は最終的な結果を超えないので、数値型に収まらなくなった時点でエラーにする
+ */
+fn binomial<N: Number>(n: u64, r: u64, config: &Config) -> Result<N, ArithError> {
+    check_size(log2_factorial(n) - log2_factorial(r) - log2_factorial(n - r))?;
+    // 小さい方の r で掛けて割るのを繰り返す。各段階の値は整数になる
+    let r = r.min(n - r);
+    let mut acc = BigInt::one();
+    let mut res = N::from_i64(1, config)?;
+    for k in 1..=r {
+        let num = &acc * &BigInt::from((n - r + k) as i128);
+        acc = num.div_rem(&BigInt::from(k as i128)).unwrap().0;
+        res = finite(N::from_bigint(&acc, config)?)?;
+    }
+    Ok(res)
+}
+
+/**
+ * 無限大や NaN になった値はオーバーフローにする
+ */
+fn finite<N: Number>(x: N) -> Result<N, ArithError> {
+    if x.is_finite() {
+        Ok(x)
+    } else {
+        Err(ArithError::Overflow)
+    }
+}
+
+/**
+ * 結果のビット数の見積もりが上限を超える場合は、計算を始める前にオーバーフローにする
+ */
+fn check_size(bits: f64) -> Result<(), ArithError> {
+    if bits > MAX_RESULT_BITS as f64 {
+        Err(ArithError::Overflow)
+    } else {
+        Ok(())
+    }
+}
+
+/**
+ * log2(n!) のスターリングの近似
+ */
+fn log2_factorial(n: u64) -> f64 {
+    if n < 2 {
+        return 0.0;
+    }
+    let n = n as f64;
+    let ln = n * n.ln() - n + 0.5 * (std::f64::consts::TAU * n).ln() + 1.0 / (12.0 * n);
+    ln / std::f64::consts::LN_2
+}
+
+/**
+ * 素数判定 (Miller-Rabin 法)
+ */
+fn is_prime(n: &BigInt) -> bool {
+    let two = BigInt::from(2i64);
+    if *n < two {
+        return false;
+    }
+
+    // 小さい素数で割り切れるかを先に調べる
+    for w in WITNESSES {
+        let w = BigInt::from(w);
+        if *n == w {
+            return true;
+        }
+        if n.rem_euclid(&w).is_zero() {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s (d は奇数) に分解する
+    let n1 = n - &BigInt::one();
+    let mut d = n1.clone();
+    let mut s = 0;
+    while !d.is_odd() {
+        d = d.div_rem(&two).unwrap().0;
+        s += 1;
+    }
+
+    WITNESSES.iter().all(|&w| {
+        let mut x = BigInt::from(w).modpow(&d, n);
+        if x == BigInt::one() || x == n1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = (&x * &x).rem_euclid(n);
+            if x == n1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_prime() {
+        let primes = (0..100)
+            .filter(|&n| is_prime(&BigInt::from(n as i64)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            primes,
+            [
+                2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79,
+                83, 89, 97
+            ]
+        );
+        // カーマイケル数と大きな素数
+        assert!(!is_prime(&BigInt::from(561i64)));
+        assert!(!is_prime(&BigInt::from(3215031751i64)));
+        assert!(is_prime(&BigInt::from(2305843009213693951i64)));
+        assert!(is_prime(
+            &BigInt::parse("170141183460469231731687303715884105727").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_binomial() {
+        let config = Config::default();
+        assert_eq!(binomial::<i32>(5, 2, &config), Ok(10));
+        assert_eq!(binomial::<i32>(10, 0, &config), Ok(1));
+        assert_eq!(
+            binomial::<BigInt>(100, 50, &config).unwrap().to_string(),
+            "100891344545564193334812497256"
+        );
+
+        // 固定長の整数型では途中で収まらなくなった時点で止める
+        assert_eq!(
+            binomial::<i32>(2_000_000_000, 1_000_000_000, &config),
+            Err(ArithError::Overflow)
+        );
+        assert_eq!(
+            binomial::<i64>(4_000_000_000, 2_000_000_000, &config),
+            Err(ArithError::Overflow)
+        );
+        assert_eq!(binomial::<i32>(33, 16, &config), Ok(1166803110));
+        assert_eq!(binomial::<i32>(34, 17, &config), Err(ArithError::Overflow));
+        assert_eq!(
+            binomial::<f64>(2000, 1000, &config),
+            Err(ArithError::Overflow)
+        );
+        // 任意精度の整数でも大きすぎる結果は求めない
+        assert_eq!(
+            binomial::<BigInt>(2_000_000_000, 1_000_000_000, &config),
+            Err(ArithError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_factorial() {
+        let config = Config::default();
+        assert_eq!(
+            falling_factorial::<f64>(170, 170, &config).map(f64::is_finite),
+            Ok(true)
+        );
+        assert_eq!(
+            falling_factorial::<f64>(171, 171, &config),
+            Err(ArithError::Overflow)
+        );
+        assert_eq!(
+            falling_factorial::<f64>(1_000_000_000_000_000, 1_000_000_000_000_000, &config),
+            Err(ArithError::Overflow)
+        );
+        assert_eq!(
+            falling_factorial::<i64>(20, 20, &config),
+            Ok(2432902008176640000)
+        );
+        assert_eq!(
+            falling_factorial::<BigInt>(10_000_000, 10_000_000, &config),
+            Err(ArithError::Overflow)
+        );
+        assert!((log2_factorial(100) - 524.765).abs() < 0.01);
+    }
+}
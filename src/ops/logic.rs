@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+use super::arith::{binary, unary};
+use super::{Arity, Op};
+use crate::config::Config;
+use crate::error::ArithError;
+use crate::number::Number;
+
+/**
+ * 比較、真偽値、条件選択の演算子
+ *
+ * 0 を偽、それ以外を真とみなし、結果の真偽値は 1 と 0 で返す
+ */
+pub(super) fn ops<N: Number>() -> Vec<Op<N>> {
+    vec![
+        Op::new(
+            "=",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a equals b",
+            |args, config| binary(args, |a, b| flag(equal(a, b), config)),
+        ),
+        Op::new(
+            "<>",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a does not equal b",
+            |args, config| binary(args, |a, b| flag(!equal(a, b), config)),
+        ),
+        Op::new(
+            "<",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a is less than b",
+            |args, config| compare(args, config, Ordering::is_lt),
+        ),
+        Op::new(
+            "<=",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a is less than or equal to b",
+            |args, config| compare(args, config, Ordering::is_le),
+        ),
+        Op::new(
+            ">",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a is greater than b",
+            |args, config| compare(args, config, Ordering::is_gt),
+        ),
+        Op::new(
+            ">=",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a is greater than or equal to b",
+            |args, config| compare(args, config, Ordering::is_ge),
+        ),
+        Op::new(
+            "and",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if both a and b are true",
+            |args, config| {
+                binary(args, |a, b| {
+                    flag(truthy(a, config)? && truthy(b, config)?, config)
+                })
+            },
+        ),
+        Op::new(
+            "or",
+            Arity::Fixed(2),
+            "( a b -- flag ) 1 if a or b is true",
+            |args, config| {
+                binary(args, |a, b| {
+                    flag(truthy(a, config)? || truthy(b, config)?, config)
+                })
+            },
+        ),
+        Op::new(
+            "not",
+            Arity::Fixed(1),
+            "( a -- flag ) 1 if a is false",
+            |args, config| unary(args, |a| flag(!truthy(a, config)?, config)),
+        ),
+        Op::new(
+            "ifte",
+            Arity::Fixed(3),
+            "( cond a b -- a|b ) a if cond is true, otherwise b",
+            select,
+        ),
+        Op::new(
+            "?",
+            Arity::Fixed(3),
+            "( cond a b -- a|b ) same as ifte",
+            select,
+        ),
+        Op::new(
+            "min",
+            Arity::Fixed(2),
+            "( a b -- min(a,b) ) smaller of a and b",
+            |args, _| {
+                binary(args, |a, b| {
+                    Ok(if b.compare(a)?.is_lt() { b } else { a }.clone())
+                })
+            },
+        ),
+        Op::new(
+            "max",
+            Arity::Fixed(2),
+            "( a b -- max(a,b) ) larger of a and b",
+            |args, _| {
+                binary(args, |a, b| {
+                    Ok(if b.compare(a)?.is_gt() { b } else { a }.clone())
+                })
+            },
+        ),
+    ]
+}
+
+/**
+ * 0 以外を真とみなす
+ */
+pub(super) fn truthy<N: Number>(x: &N, config: &Config) -> Result<bool, ArithError> {
+    Ok(!equal(x, &N::from_i64(0, config)?))
+}
+
+/**
+ * 2 つの値が等しいかどうか
+ *
+ * 大小を比べられる数値は表現 (固定小数点数の桁数など) によらず値で比べ、NaN は何とも等しくない。
+ * 大小のない複素数は実部と虚部をそれぞれ比べる
+ */
+fn equal<N: Number>(a: &N, b: &N) -> bool {
+    match a.compare(b) {
+        Ok(ord) => ord.is_eq(),
+        Err(ArithError::Unsupported) => a == b,
+        Err(_) => false,
+    }
+}
+
+/**
+ * 真偽値を 1 と 0 で表す
+ */
+fn flag<N: Number>(b: bool, config: &Config) -> Result<N, ArithError> {
+    N::from_i64(b as i64, config)
+}
+
+/**
+ * 2 つの値の大小関係を真偽値にする
+ */
+fn compare<N: Number>(
+    args: Vec<N>,
+    config: &Config,
+    pred: fn(Ordering) -> bool,
+) -> Result<Vec<N>, ArithError> {
+    binary(args, |a, b| flag(pred(a.compare(b)?), config))
+}
+
+/**
+ * 条件に応じて 2 つの値の一方を選ぶ
+ */
+fn select<N: Number>(mut args: Vec<N>, config: &Config) -> Result<Vec<N>, ArithError> {
+    let b = args.pop().unwrap();
+    let a = args.pop().unwrap();
+    Ok(vec![if truthy(&args[0], config)? { a } else { b }])
+}
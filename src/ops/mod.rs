@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::calculator::Context;
+use crate::config::Config;
+use crate::error::ArithError;
+use crate::number::Number;
+use crate::value::Value;
+
+mod arith;
+mod bits;
+mod control;
+mod history;
+mod integer;
+mod logic;
+mod sci;
+mod session;
+mod stack;
+mod vars;
+
+/**
+ * 演算子がスタックから取る値の個数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    // 決まった個数の値を取る
+    Fixed(usize),
+    // スタック上の全ての値を取る。最低限必要な個数を持つ
+    Variadic(usize),
+}
+
+impl Arity {
+    // 最低限必要な値の個数
+    pub fn min(&self) -> usize {
+        match *self {
+            Arity::Fixed(n) | Arity::Variadic(n) => n,
+        }
+    }
+}
+
+/**
+ * 演算子
+ *
+ * 評価器は `arity` に従ってスタックに十分な値があることを確かめてから取り出し、
+ * `apply` の返した値をスタックに積み直す。
+ * `apply` の間は残りのスタックを `Context` から操作でき、クォーテーションも評価できる
+ */
+pub trait Operator<N>: Send + Sync {
+    // トークンとして書く名前
+    fn name(&self) -> &str;
+
+    // スタックから取る値の個数
+    fn arity(&self) -> Arity;
+
+    // 取り出した値に演算を適用する
+    fn apply(&self, args: Vec<Value<N>>, ctx: &mut Context<N>)
+        -> Result<Vec<Value<N>>, ArithError>;
+
+    // 一覧表示用の説明
+    fn help(&self) -> &str;
+}
+
+/**
+ * 関数ポインタで定義する数値の演算子
+ *
+ * 引数が数値でない場合は `ArithError::Type` にする
+ */
+pub struct Op<N> {
+    name: &'static str,
+    arity: Arity,
+    help: &'static str,
+    apply: fn(Vec<N>, &Config) -> Result<Vec<N>, ArithError>,
+}
+
+impl<N> Op<N> {
+    // 名前、値の個数、説明、処理から演算子を定義する
+    pub fn new(
+        name: &'static str,
+        arity: Arity,
+        help: &'static str,
+        apply: fn(Vec<N>, &Config) -> Result<Vec<N>, ArithError>,
+    ) -> Self {
+        Self {
+            name,
+            arity,
+            help,
+            apply,
+        }
+    }
+}
+
+impl<N: Number> Operator<N> for Op<N> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn apply(
+        &self,
+        args: Vec<Value<N>>,
+        ctx: &mut Context<N>,
+    ) -> Result<Vec<Value<N>>, ArithError> {
+        let args = args
+            .into_iter()
+            .map(Value::into_num)
+            .collect::<Result<Vec<_>, _>>()?;
+        let res = (self.apply)(args, ctx.config())?;
+        Ok(res.into_iter().map(Value::Num).collect())
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
+}
+
+// 値を扱う演算子の処理
+type ValueFn<N> = fn(Vec<Value<N>>, &mut Context<N>) -> Result<Vec<Value<N>>, ArithError>;
+
+/**
+ * 関数ポインタで定義する、数値以外の値も扱う演算子
+ *
+ * スタック操作のワードとコンビネータに使う
+ */
+pub struct ValueOp<N> {
+    name: &'static str,
+    arity: Arity,
+    help: &'static str,
+    apply: ValueFn<N>,
+}
+
+impl<N> ValueOp<N> {
+    // 名前、値の個数、説明、処理から演算子を定義する
+    pub fn new(name: &'static str, arity: Arity, help: &'static str, apply: ValueFn<N>) -> Self {
+        Self {
+            name,
+            arity,
+            help,
+            apply,
+        }
+    }
+}
+
+impl<N: Number> Operator<N> for ValueOp<N> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn apply(
+        &self,
+        args: Vec<Value<N>>,
+        ctx: &mut Context<N>,
+    ) -> Result<Vec<Value<N>>, ArithError> {
+        (self.apply)(args, ctx)
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
+}
+
+/**
+ * 名前から演算子を引く表
+ *
+ * 別名は登録した演算子の名前を指し、引くときに名前に読み替える
+ */
+pub struct OperatorRegistry<N> {
+    ops: HashMap<String, Arc<dyn Operator<N>>>,
+    aliases: HashMap<String, String>,
+}
+
+impl<N: Number> OperatorRegistry<N> {
+    // 空の表を作る
+    pub fn new() -> Self {
+        Self {
+            ops: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    // 組み込みの演算子を登録した表を作る
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        let builtins = arith::ops()
+            .into_iter()
+            .chain(integer::ops())
+            .chain(sci::ops())
+            .chain(bits::ops())
+            .chain(logic::ops());
+        for op in builtins {
+            registry.register(op);
+        }
+        let value_ops = stack::ops()
+            .into_iter()
+            .chain(control::ops())
+            .chain(vars::ops())
+            .chain(history::ops())
+            .chain(session::ops());
+        for op in value_ops {
+            registry.register(op);
+        }
+        // `xor` は真偽値の演算子と名前が重ならないので `b` を付けずにも書ける
+        registry.alias("xor", "bxor");
+        registry
+    }
+
+    // 演算子を登録する。同じ名前の演算子があれば置き換えて、元の演算子を返す
+    pub fn register(&mut self, op: impl Operator<N> + 'static) -> Option<Arc<dyn Operator<N>>> {
+        self.ops.insert(op.name().to_string(), Arc::new(op))
+    }
+
+    // 演算子の登録を取り消す
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Operator<N>>> {
+        self.ops.remove(name)
+    }
+
+    // 演算子 `name` の別名を登録する。同じ名前の演算子があればそちらが優先する
+    pub fn alias(&mut self, alias: &str, name: &str) {
+        self.aliases.insert(alias.to_string(), name.to_string());
+    }
+
+    // 名前から演算子を引く。演算子がなければ別名として引く
+    pub fn get(&self, name: &str) -> Option<&dyn Operator<N>> {
+        let op = match self.ops.get(name) {
+            Some(op) => op,
+            None => self.ops.get(self.aliases.get(name)?)?,
+        };
+        Some(op.as_ref())
+    }
+
+    // 名前順に並べた別名と、それが指す演算子の名前
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut aliases = self
+            .aliases
+            .iter()
+            .map(|(alias, name)| (alias.as_str(), name.as_str()))
+            .collect::<Vec<_>>();
+        aliases.sort();
+        aliases.into_iter()
+    }
+
+    // 名前順に並べた演算子
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Operator<N>> {
+        let mut ops = self.ops.values().map(|op| op.as_ref()).collect::<Vec<_>>();
+        ops.sort_by(|a, b| a.name().cmp(b.name()));
+        ops.into_iter()
+    }
+}
+
+impl<N: Number> Default for OperatorRegistry<N> {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl<N> Clone for OperatorRegistry<N> {
+    fn clone(&self) -> Self {
+        Self {
+            ops: self.ops.clone(),
+            aliases: self.aliases.clone(),
+        }
+    }
+}
+
+impl<N> fmt::Debug for OperatorRegistry<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.ops.keys().collect::<Vec<_>>();
+        names.sort();
+        f.debug_struct("OperatorRegistry")
+            .field("ops", &names)
+            .field("aliases", &self.aliases)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::RpnCalculator;
+    use crate::number::Decimal;
+
+    // 消費税を加算する演算子
+    struct Vat;
+
+    impl Operator<Decimal> for Vat {
+        fn name(&self) -> &str {
+            "vat"
+        }
+
+        fn arity(&self) -> Arity {
+            Arity::Fixed(1)
+        }
+
+        fn apply(
+            &self,
+            args: Vec<Value<Decimal>>,
+            ctx: &mut Context<Decimal>,
+        ) -> Result<Vec<Value<Decimal>>, ArithError> {
+            let config = ctx.config();
+            let rate = Decimal::parse("1.10", config).unwrap();
+            let x = args[0].as_num().ok_or(ArithError::Type)?;
+            Ok(vec![Value::Num(x.mul(&rate, config)?)])
+        }
+
+        fn help(&self) -> &str {
+            "( x -- x*1.1 ) add 10% VAT"
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = OperatorRegistry::with_builtins();
+        assert!(registry.register(Vat).is_none());
+        assert_eq!(
+            registry.get("vat").unwrap().help(),
+            "( x -- x*1.1 ) add 10% VAT"
+        );
+
+        let mut calc = RpnCalculator::with_registry(Config::default(), registry);
+        assert_eq!(calc.eval("100 vat").unwrap().to_string(), "110.00");
+        assert!(calc.eval("vat").is_err());
+    }
+
+    #[test]
+    fn test_override() {
+        let mut calc = RpnCalculator::<i32>::default();
+        let prev = calc.registry_mut().register(Op::new(
+            "+",
+            Arity::Fixed(2),
+            "( x y -- x ) ignore y",
+            |mut args, _| {
+                args.pop();
+                Ok(args)
+            },
+        ));
+        assert_eq!(prev.unwrap().name(), "+");
+        assert_eq!(calc.eval("1 2 +").unwrap(), 1);
+
+        calc.registry_mut().unregister("+");
+        assert!(calc.eval("1 2 +").is_err());
+
+        let names = OperatorRegistry::<i32>::new().iter().count();
+        assert_eq!(names, 0);
+    }
+
+    #[test]
+    fn test_alias() {
+        // 別名は演算子の一覧に重ねて出さない
+        let mut registry = OperatorRegistry::<i32>::with_builtins();
+        assert_eq!(registry.get("xor").unwrap().name(), "bxor");
+        assert!(registry.iter().all(|op| op.name() != "xor"));
+        assert_eq!(registry.aliases().collect::<Vec<_>>(), [("xor", "bxor")]);
+
+        // 指す演算子を置き換えると別名も従い、取り消すと引けなくなる
+        registry.register(Op::new("bxor", Arity::Fixed(2), "( x y -- 0 )", |_, _| {
+            Ok(vec![0])
+        }));
+        let mut calc = RpnCalculator::with_registry(Config::default(), registry.clone());
+        assert_eq!(calc.eval("6 3 xor").unwrap(), 0);
+        registry.unregister("bxor");
+        assert!(registry.get("xor").is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use std::f64::consts;
+
+use super::arith::{binary, unary};
+use super::{Arity, Op};
+use crate::number::{Function, Number};
+
+/**
+ * 科学関数と定数
+ *
+ * 三角関数と逆三角関数は設定の角度の単位に従う
+ */
+pub(super) fn ops<N: Number>() -> Vec<Op<N>> {
+    vec![
+        Op::new(
+            "sin",
+            Arity::Fixed(1),
+            "( x -- sin(x) ) sine",
+            |args, config| unary(args, |x| x.call(Function::Sin, config)),
+        ),
+        Op::new(
+            "cos",
+            Arity::Fixed(1),
+            "( x -- cos(x) ) cosine",
+            |args, config| unary(args, |x| x.call(Function::Cos, config)),
+        ),
+        Op::new(
+            "tan",
+            Arity::Fixed(1),
+            "( x -- tan(x) ) tangent",
+            |args, config| unary(args, |x| x.call(Function::Tan, config)),
+        ),
+        Op::new(
+            "asin",
+            Arity::Fixed(1),
+            "( x -- asin(x) ) arc sine",
+            |args, config| unary(args, |x| x.call(Function::Asin, config)),
+        ),
+        Op::new(
+            "acos",
+            Arity::Fixed(1),
+            "( x -- acos(x) ) arc cosine",
+            |args, config| unary(args, |x| x.call(Function::Acos, config)),
+        ),
+        Op::new(
+            "atan",
+            Arity::Fixed(1),
+            "( x -- atan(x) ) arc tangent",
+            |args, config| unary(args, |x| x.call(Function::Atan, config)),
+        ),
+        Op::new(
+            "atan2",
+            Arity::Fixed(2),
+            "( y x -- atan2(y,x) ) angle of the point (x, y)",
+            |args, config| binary(args, |y, x| y.atan2(x, config)),
+        ),
+        Op::new(
+            "sinh",
+            Arity::Fixed(1),
+            "( x -- sinh(x) ) hyperbolic sine",
+            |args, config| unary(args, |x| x.call(Function::Sinh, config)),
+        ),
+        Op::new(
+            "cosh",
+            Arity::Fixed(1),
+            "( x -- cosh(x) ) hyperbolic cosine",
+            |args, config| unary(args, |x| x.call(Function::Cosh, config)),
+        ),
+        Op::new(
+            "tanh",
+            Arity::Fixed(1),
+            "( x -- tanh(x) ) hyperbolic tangent",
+            |args, config| unary(args, |x| x.call(Function::Tanh, config)),
+        ),
+        Op::new(
+            "exp",
+            Arity::Fixed(1),
+            "( x -- e^x ) exponential",
+            |args, config| unary(args, |x| x.call(Function::Exp, config)),
+        ),
+        Op::new(
+            "ln",
+            Arity::Fixed(1),
+            "( x -- ln(x) ) natural logarithm",
+            |args, config| unary(args, |x| x.call(Function::Ln, config)),
+        ),
+        Op::new(
+            "log10",
+            Arity::Fixed(1),
+            "( x -- log10(x) ) common logarithm",
+            |args, config| unary(args, |x| x.call(Function::Log10, config)),
+        ),
+        Op::new(
+            "log2",
+            Arity::Fixed(1),
+            "( x -- log2(x) ) binary logarithm",
+            |args, config| unary(args, |x| x.call(Function::Log2, config)),
+        ),
+        Op::new(
+            "floor",
+            Arity::Fixed(1),
+            "( x -- floor(x) ) round toward negative infinity",
+            |args, config| unary(args, |x| x.call(Function::Floor, config)),
+        ),
+        Op::new(
+            "ceil",
+            Arity::Fixed(1),
+            "( x -- ceil(x) ) round toward positive infinity",
+            |args, config| unary(args, |x| x.call(Function::Ceil, config)),
+        ),
+        Op::new(
+            "round",
+            Arity::Fixed(1),
+            "( x -- round(x) ) round half away from zero",
+            |args, config| unary(args, |x| x.call(Function::Round, config)),
+        ),
+        Op::new(
+            "trunc",
+            Arity::Fixed(1),
+            "( x -- trunc(x) ) round toward zero",
+            |args, config| unary(args, |x| x.call(Function::Trunc, config)),
+        ),
+        Op::new(
+            "pi",
+            Arity::Fixed(0),
+            "( -- pi ) ratio of circumference to diameter",
+            |_, config| Ok(vec![N::from_f64(consts::PI, config)?]),
+        ),
+        Op::new(
+            "e",
+            Arity::Fixed(0),
+            "( -- e ) base of the natural logarithm",
+            |_, config| Ok(vec![N::from_f64(consts::E, config)?]),
+        ),
+        Op::new(
+            "tau",
+            Arity::Fixed(0),
+            "( -- tau ) ratio of circumference to radius",
+            |_, config| Ok(vec![N::from_f64(consts::TAU, config)?]),
+        ),
+    ]
+}
@@ -0,0 +1,34 @@
+use std::path::Path;
+
+use super::{Arity, ValueOp};
+use crate::number::Number;
+
+/**
+ * セッションを保存・読み込みするワード
+ *
+ * ファイルのパスは `"path"` または `'path` と書いて積む
+ */
+pub(super) fn ops<N: Number>() -> Vec<ValueOp<N>> {
+    vec![
+        ValueOp::new(
+            "save",
+            Arity::Fixed(1),
+            "( path -- ) save the session; existing files must be sessions",
+            |mut args, ctx| {
+                let path = args.pop().unwrap().into_text()?;
+                ctx.save_session(Path::new(&path))?;
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "load",
+            Arity::Fixed(1),
+            "( path -- ... ) restore a saved session",
+            |mut args, ctx| {
+                let path = args.pop().unwrap().into_text()?;
+                ctx.load_session(Path::new(&path))?;
+                Ok(vec![])
+            },
+        ),
+    ]
+}
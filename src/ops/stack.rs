@@ -0,0 +1,130 @@
+use super::{Arity, ValueOp};
+use crate::error::ArithError;
+use crate::number::Number;
+use crate::value::Value;
+
+/**
+ * スタック操作のワード
+ *
+ * 数値以外の値もそのまま操作する
+ */
+pub(super) fn ops<N: Number>() -> Vec<ValueOp<N>> {
+    vec![
+        ValueOp::new(
+            "dup",
+            Arity::Fixed(1),
+            "( a -- a a ) duplicate the top value",
+            |args, _| Ok(vec![args[0].clone(), args[0].clone()]),
+        ),
+        ValueOp::new(
+            "drop",
+            Arity::Fixed(1),
+            "( a -- ) discard the top value",
+            |_, _| Ok(vec![]),
+        ),
+        ValueOp::new(
+            "swap",
+            Arity::Fixed(2),
+            "( a b -- b a ) exchange the top two values",
+            |mut args, _| {
+                args.swap(0, 1);
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "over",
+            Arity::Fixed(2),
+            "( a b -- a b a ) copy the second value to the top",
+            |mut args, _| {
+                args.push(args[0].clone());
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "rot",
+            Arity::Fixed(3),
+            "( a b c -- b c a ) rotate the third value to the top",
+            |mut args, _| {
+                args.rotate_left(1);
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "-rot",
+            Arity::Fixed(3),
+            "( a b c -- c a b ) rotate the top value to the third",
+            |mut args, _| {
+                args.rotate_right(1);
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "nip",
+            Arity::Fixed(2),
+            "( a b -- b ) discard the second value",
+            |mut args, _| {
+                args.remove(0);
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "tuck",
+            Arity::Fixed(2),
+            "( a b -- b a b ) copy the top value below the second",
+            |mut args, _| {
+                args.insert(0, args[1].clone());
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "pick",
+            Arity::Variadic(1),
+            "( xu ... x0 u -- xu ... x0 xu ) copy the u-th value to the top",
+            |mut args, _| {
+                let i = index(&mut args)?;
+                args.push(args[i].clone());
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "roll",
+            Arity::Variadic(1),
+            "( xu ... x0 u -- ... x0 xu ) move the u-th value to the top",
+            |mut args, _| {
+                let i = index(&mut args)?;
+                let x = args.remove(i);
+                args.push(x);
+                Ok(args)
+            },
+        ),
+        ValueOp::new(
+            "clear",
+            Arity::Variadic(0),
+            "( ... -- ) discard all values",
+            |_, _| Ok(vec![]),
+        ),
+        ValueOp::new(
+            "depth",
+            Arity::Variadic(0),
+            "( ... -- ... n ) push the number of values on the stack",
+            |mut args, ctx| {
+                args.push(Value::Num(N::from_i64(args.len() as i64, ctx.config())?));
+                Ok(args)
+            },
+        ),
+    ]
+}
+
+/**
+ * スタックの先頭から `u` を取り出し、`u` 番目の値の添字を返す
+ */
+fn index<N: Number>(args: &mut Vec<Value<N>>) -> Result<usize, ArithError> {
+    let u = args.pop().unwrap().into_num()?;
+    let u = u
+        .to_i64()
+        .and_then(|u| usize::try_from(u).ok())
+        .ok_or(ArithError::Domain)?;
+    args.len()
+        .checked_sub(u + 1)
+        .ok_or(ArithError::StackUnderflow)
+}
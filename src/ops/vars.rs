@@ -0,0 +1,44 @@
+use super::{Arity, ValueOp};
+use crate::number::Number;
+use crate::value::{Quotation, Value};
+
+/**
+ * 変数のワード
+ *
+ * 変数名は `'name` と書いて積む。`rcl` と `sto` の直前では、ワードでも演算子でもない名前の `'` を省ける
+ */
+pub(super) fn ops<N: Number>() -> Vec<ValueOp<N>> {
+    vec![
+        ValueOp::new(
+            "sto",
+            Arity::Fixed(2),
+            "( x 'name -- ) store x in the variable; `x name sto` also works",
+            |mut args, ctx| {
+                let name = args.pop().unwrap().into_symbol()?;
+                ctx.set_var(name, args.pop().unwrap());
+                Ok(vec![])
+            },
+        ),
+        ValueOp::new(
+            "rcl",
+            Arity::Fixed(1),
+            "( 'name -- x ) push the value of the variable; `name rcl` also works",
+            |mut args, ctx| {
+                let name = args.pop().unwrap().into_symbol()?;
+                Ok(vec![ctx.var(&name)?.clone()])
+            },
+        ),
+        ValueOp::new(
+            "vars",
+            Arity::Fixed(0),
+            "( -- [ 'name x ... ] ) list the variables and their values",
+            |_, ctx| {
+                let list = ctx
+                    .vars()
+                    .flat_map(|(name, value)| [Value::Symbol(name.to_string()), value.clone()])
+                    .collect();
+                Ok(vec![Value::Quote(Quotation::from_values(list)?)])
+            },
+        ),
+    ]
+}
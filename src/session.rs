@@ -0,0 +1,478 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::calculator::parse;
+use crate::config::{
+    Angle, Arithmetic, ComplexFormat, Config, FloatFormat, Radix, RationalFormat, Rounding,
+    WordSize, MAX_SCALE,
+};
+use crate::error::SessionError;
+use crate::number::Number;
+use crate::value::{Quotation, Value};
+
+// セッションファイルの先頭の見出し
+const HEADER: &str = "rpncalc-session";
+
+// 書き出す形式のバージョン。読み込めるのも同じバージョンだけ
+const VERSION: u32 = 1;
+
+/**
+ * 保存・読み込みするセッションの状態
+ *
+ * ファイルは 1 行に 1 項目のテキストで、見出しと数値の種類の後に設定、ワード、変数、スタックを書く。
+ * 値は式と同じ書き方なので、読み込むときは先に書いた設定に従って解釈する
+ *
+ * ```text
+ * rpncalc-session 1
+ * number i32
+ * angle deg
+ * word sq dup *
+ * var 'x 5
+ * stack 1 2 [ 3 4 + ]
+ * ```
+ */
+#[derive(Debug)]
+pub(crate) struct Session<N> {
+    pub(crate) config: Config,
+    pub(crate) words: Vec<(String, Quotation<N>)>,
+    pub(crate) vars: Vec<(String, Value<N>)>,
+    pub(crate) stack: Vec<Value<N>>,
+}
+
+impl<N: Number> Session<N> {
+    // 計算機の状態を写し取る
+    pub(crate) fn new<'a>(
+        config: &Config,
+        words: impl Iterator<Item = (&'a str, &'a Quotation<N>)>,
+        vars: impl Iterator<Item = (&'a str, &'a Value<N>)>,
+        stack: &[Value<N>],
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            words: words
+                .map(|(name, body)| (name.to_string(), body.clone()))
+                .collect(),
+            vars: vars
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            stack: stack.to_vec(),
+        }
+    }
+
+    // ファイルに書き出す。セッションファイルでも空でもない既存のファイルは上書きしない。
+    // 書き出しが途中で失敗しても元のファイルが残るように、一時ファイルに書いてから置き換える
+    pub(crate) fn save(&self, path: &Path) -> Result<(), SessionError> {
+        match fs::File::open(path) {
+            Ok(file) => {
+                let mut head = Vec::new();
+                file.take(HEADER.len() as u64)
+                    .read_to_end(&mut head)
+                    .map_err(|e| io_error(path, e))?;
+                if !head.is_empty() && head != HEADER.as_bytes() {
+                    return Err(SessionError::NotSession);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(io_error(path, e)),
+        }
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(format!(".{}.tmp", std::process::id()));
+        let temp = PathBuf::from(temp);
+        fs::write(&temp, self.to_text())
+            .and_then(|()| fs::rename(&temp, path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp);
+                io_error(path, e)
+            })
+    }
+
+    // 値を今の設定で書き出して読み直す。固定小数点数の桁数などが設定に揃う
+    pub(crate) fn reread(&self) -> Result<Self, SessionError> {
+        Self::from_text(&self.to_text())
+    }
+
+    // ファイルから読み込む
+    pub(crate) fn load(path: &Path) -> Result<Self, SessionError> {
+        let text = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        Self::from_text(&text)
+    }
+
+    // セッションファイルの内容
+    fn to_text(&self) -> String {
+        let config = &self.config;
+        let precision = config.precision.map(|p| p.to_string());
+        let radix = config.radix.iter().map(|&r| name_of(r));
+        let mut lines = vec![
+            entry(HEADER, [VERSION]),
+            entry("number", [N::KIND]),
+            entry("arithmetic", [name_of(config.arithmetic)]),
+            entry("precision", [precision.as_deref().unwrap_or("none")]),
+            entry("float-format", [name_of(config.float_format)]),
+            entry("rational-format", [name_of(config.rational_format)]),
+            entry("scale", [config.scale]),
+            entry("rounding", [name_of(config.rounding)]),
+            entry("complex-format", [name_of(config.complex_format)]),
+            entry("angle", [name_of(config.angle)]),
+            entry("word-size", [name_of(config.word_size)]),
+            entry("signed", [config.signed]),
+            entry("radix", radix),
+        ];
+        for (name, body) in &self.words {
+            let terms = body.terms().iter().map(ToString::to_string);
+            lines.push(entry("word", [name.clone()].into_iter().chain(terms)));
+        }
+        for (name, value) in &self.vars {
+            let name = Value::<N>::Symbol(name.clone());
+            lines.push(entry("var", [name.to_string(), value.to_string()]));
+        }
+        lines.push(entry("stack", &self.stack));
+        lines.join("\n") + "\n"
+    }
+
+    // セッションファイルの内容を解釈する
+    fn from_text(text: &str) -> Result<Self, SessionError> {
+        // 空行と `#` で始まる行は読み飛ばす
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        // 見出しとバージョン
+        let version = match lines.next().map(|(_, line)| split_entry(line)) {
+            Some((HEADER, version)) => version.parse().map_err(|_| SessionError::NotSession)?,
+            _ => return Err(SessionError::NotSession),
+        };
+        if version != VERSION {
+            return Err(SessionError::Version {
+                found: version,
+                expected: VERSION,
+            });
+        }
+
+        // 数値の種類は見出しの直後に書く
+        match lines.next() {
+            Some((_, line)) if split_entry(line).0 == "number" => {
+                let kind = split_entry(line).1;
+                if kind != N::KIND {
+                    return Err(SessionError::NumberKind {
+                        found: kind.to_string(),
+                        expected: N::KIND.to_string(),
+                    });
+                }
+            }
+            Some((line, _)) => return Err(corrupt(line, "expected `number`")),
+            None => return Err(SessionError::NotSession),
+        }
+
+        let mut session = Self {
+            config: Config::default(),
+            words: Vec::new(),
+            vars: Vec::new(),
+            stack: Vec::new(),
+        };
+        for (line, text) in lines {
+            let (key, rest) = split_entry(text);
+            let config = &mut session.config;
+            match key {
+                "arithmetic" => config.arithmetic = setting(line, rest)?,
+                "precision" => {
+                    config.precision = match rest {
+                        "none" => None,
+                        p => Some(scalar(line, p)?),
+                    }
+                }
+                "float-format" => config.float_format = setting(line, rest)?,
+                "rational-format" => config.rational_format = setting(line, rest)?,
+                "scale" => {
+                    config.scale = scalar(line, rest)?;
+                    if config.scale > MAX_SCALE {
+                        return Err(corrupt(line, "scale too large"));
+                    }
+                }
+                "rounding" => config.rounding = setting(line, rest)?,
+                "complex-format" => config.complex_format = setting(line, rest)?,
+                "angle" => config.angle = setting(line, rest)?,
+                "word-size" => config.word_size = setting(line, rest)?,
+                "signed" => config.signed = scalar(line, rest)?,
+                "radix" => {
+                    config.radix = rest
+                        .split_whitespace()
+                        .map(|r| setting(line, r))
+                        .collect::<Result<_, _>>()?;
+                    if config.radix.is_empty() {
+                        return Err(corrupt(line, "no radix"));
+                    }
+                }
+                "word" => {
+                    let (name, body) = split_entry(rest);
+                    if name.is_empty() {
+                        return Err(corrupt(line, "word without a name"));
+                    }
+                    let body = program(line, body, config)?;
+                    session.words.push((name.to_string(), body));
+                }
+                "var" => match <[_; 2]>::try_from(values(line, rest, config)?) {
+                    Ok([Value::Symbol(name), value]) => session.vars.push((name, value)),
+                    _ => return Err(corrupt(line, "expected `var 'NAME VALUE`")),
+                },
+                "stack" => session.stack = values(line, rest, config)?,
+                _ => return Err(corrupt(line, &format!("unknown entry `{}`", key))),
+            }
+        }
+        Ok(session)
+    }
+}
+
+/**
+ * 設定の値とファイルに書く名前の対応
+ */
+trait Setting: Copy + PartialEq + 'static {
+    const NAMES: &'static [(Self, &'static str)];
+}
+
+impl Setting for Arithmetic {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Arithmetic::Checked, "checked"),
+        (Arithmetic::Wrapping, "wrapping"),
+        (Arithmetic::Saturating, "saturating"),
+    ];
+}
+
+impl Setting for FloatFormat {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (FloatFormat::Fixed, "fixed"),
+        (FloatFormat::Sci, "sci"),
+        (FloatFormat::Auto, "auto"),
+    ];
+}
+
+impl Setting for RationalFormat {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (RationalFormat::Fraction, "fraction"),
+        (RationalFormat::Mixed, "mixed"),
+        (RationalFormat::Decimal, "decimal"),
+    ];
+}
+
+impl Setting for Rounding {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Rounding::HalfEven, "half-even"),
+        (Rounding::HalfUp, "half-up"),
+        (Rounding::Down, "down"),
+        (Rounding::Up, "up"),
+        (Rounding::Ceiling, "ceiling"),
+        (Rounding::Floor, "floor"),
+    ];
+}
+
+impl Setting for ComplexFormat {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (ComplexFormat::Rect, "rect"),
+        (ComplexFormat::Polar, "polar"),
+    ];
+}
+
+impl Setting for Angle {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Angle::Rad, "rad"),
+        (Angle::Deg, "deg"),
+        (Angle::Grad, "grad"),
+    ];
+}
+
+impl Setting for WordSize {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (WordSize::W8, "8"),
+        (WordSize::W16, "16"),
+        (WordSize::W32, "32"),
+        (WordSize::W64, "64"),
+    ];
+}
+
+impl Setting for Radix {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Radix::Dec, "dec"),
+        (Radix::Hex, "hex"),
+        (Radix::Oct, "oct"),
+        (Radix::Bin, "bin"),
+    ];
+}
+
+// 設定の値の名前
+fn name_of<T: Setting>(value: T) -> &'static str {
+    T::NAMES
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, name)| *name)
+        .unwrap()
+}
+
+// 名前から設定の値を引く
+fn setting<T: Setting>(line: usize, name: &str) -> Result<T, SessionError> {
+    T::NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(v, _)| *v)
+        .ok_or_else(|| corrupt(line, &format!("unknown setting `{}`", name)))
+}
+
+// 数値や真偽値の設定を解釈する
+fn scalar<T: std::str::FromStr>(line: usize, s: &str) -> Result<T, SessionError> {
+    s.parse()
+        .map_err(|_| corrupt(line, &format!("invalid setting `{}`", s)))
+}
+
+// 式を組み立てる。ワードの定義は書けない
+fn program<N: Number>(line: usize, s: &str, config: &Config) -> Result<Quotation<N>, SessionError> {
+    match parse(s, config) {
+        Ok((program, definitions)) if definitions.is_empty() => Ok(program),
+        Ok(_) => Err(corrupt(line, "unexpected definition")),
+        Err(e) => Err(corrupt(line, &e.to_string())),
+    }
+}
+
+// 値の並びを解釈する
+fn values<N: Number>(line: usize, s: &str, config: &Config) -> Result<Vec<Value<N>>, SessionError> {
+    program(line, s, config)?
+        .values()
+        .map_err(|_| corrupt(line, "expected values"))
+}
+
+// 名前と空白で区切った値を 1 行にする
+fn entry<T: fmt::Display>(key: &str, items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .fold(key.to_string(), |line, item| format!("{} {}", line, item))
+}
+
+// 行を最初の空白で項目の名前と残りに分ける
+fn split_entry(line: &str) -> (&str, &str) {
+    match line.split_once(char::is_whitespace) {
+        Some((key, rest)) => (key, rest.trim()),
+        None => (line, ""),
+    }
+}
+
+fn corrupt(line: usize, reason: &str) -> SessionError {
+    SessionError::Corrupt {
+        line,
+        reason: reason.to_string(),
+    }
+}
+
+fn io_error(path: &Path, e: io::Error) -> SessionError {
+    SessionError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::{Complex, Decimal, Rational};
+
+    // 計算機の状態をテキストにして読み直す
+    fn round_trip<N: Number>(setup: &str) -> (String, Session<N>) {
+        let mut calc = crate::RpnCalculator::<N>::default();
+        calc.eval_session(setup).unwrap();
+        let session = Session::new(calc.config(), calc.words(), calc.vars(), calc.stack());
+        let text = session.to_text();
+        let read = Session::<N>::from_text(&text).unwrap();
+        assert_eq!(read.stack, calc.stack());
+        assert_eq!(read.to_text(), text);
+        (text, read)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (text, session) = round_trip::<i32>(
+            ": sq dup * ; 5 =x 1 2 [ 3 sq + ] 'name \"a \\\"b\\\"\" '\"c d\" sto",
+        );
+        assert!(text.starts_with("rpncalc-session 1\nnumber i32\n"));
+        assert!(text.contains("\nword sq dup *\nvar '\"c d\" \"a \\\"b\\\"\"\nvar 'x 5\n"));
+        assert!(text.ends_with("\nstack 1 2 [ 3 sq + ] 'name\n"));
+        assert_eq!(session.words[0].0, "sq");
+        assert_eq!(session.vars[1], ("x".to_string(), Value::Num(5)));
+
+        round_trip::<f64>("0.1 1e300 -2.5 inf");
+        round_trip::<Rational>("1 3 / -7/2");
+        round_trip::<Decimal>("1.25 -0.5");
+        round_trip::<Complex>("1+2i -3i 0.5");
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut config = Config {
+            precision: Some(4),
+            angle: Angle::Deg,
+            rounding: Rounding::HalfUp,
+            word_size: WordSize::W16,
+            signed: false,
+            radix: vec![Radix::Hex, Radix::Bin],
+            ..Config::default()
+        };
+        let session = Session::<i64>::new(&config, [].into_iter(), [].into_iter(), &[]);
+        let text = session.to_text();
+        assert!(text.contains("\nprecision 4\n"));
+        assert!(text.contains("\nrounding half-up\n"));
+        assert!(text.contains("\nradix hex bin\n"));
+        assert!(text.ends_with("\nstack\n"));
+
+        let read = Session::<i64>::from_text(&text).unwrap().config;
+        assert_eq!(read.precision, Some(4));
+        assert_eq!(read.angle, Angle::Deg);
+        assert_eq!(read.word_size, WordSize::W16);
+        assert!(!read.signed);
+        assert_eq!(read.radix, [Radix::Hex, Radix::Bin]);
+
+        // 書かれていない設定は既定値
+        config = Session::<i64>::from_text("rpncalc-session 1\nnumber i64\n")
+            .unwrap()
+            .config;
+        assert_eq!(config.angle, Angle::Rad);
+    }
+
+    #[test]
+    fn test_errors() {
+        let read = |text: &str| Session::<i32>::from_text(text).unwrap_err();
+        assert_eq!(read(""), SessionError::NotSession);
+        assert_eq!(read("1 2 +\n"), SessionError::NotSession);
+        assert_eq!(
+            read("rpncalc-session 2\nnumber i32\n"),
+            SessionError::Version {
+                found: 2,
+                expected: 1
+            }
+        );
+        assert_eq!(
+            read("rpncalc-session 1\nnumber rational\n"),
+            SessionError::NumberKind {
+                found: "rational".to_string(),
+                expected: "i32".to_string()
+            }
+        );
+        let corrupt = |text: &str| match read(text) {
+            SessionError::Corrupt { line, .. } => line,
+            e => panic!("{:?}", e),
+        };
+        assert_eq!(corrupt("rpncalc-session 1\nstack 1\n"), 2);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\n\nangle turn\n"), 4);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nstack 1 +\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nstack [ 1\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nvar 'x\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nvar x 1\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nword : a ;\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\ncolor red\n"), 3);
+        assert_eq!(corrupt("rpncalc-session 1\nnumber i32\nscale 70000\n"), 3);
+
+        let path = std::env::temp_dir().join("rpncalc-no-such-dir/session");
+        assert!(matches!(
+            Session::<i32>::load(&path),
+            Err(SessionError::Io { .. })
+        ));
+    }
+}
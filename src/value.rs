@@ -0,0 +1,247 @@
+use std::fmt;
+
+use crate::config::Config;
+use crate::error::ArithError;
+use crate::lexer::{is_bare_symbol, quote, Span};
+use crate::number::Number;
+
+/**
+ * スタックに積む値
+ */
+#[derive(Clone, PartialEq)]
+pub enum Value<N> {
+    // 数値
+    Num(N),
+    // `[ ... ]` で囲んだクォーテーション
+    Quote(Quotation<N>),
+    // `'name` または `'"..."` と書いた変数名
+    Symbol(String),
+    // `"..."` と書いた文字列
+    Str(String),
+}
+
+impl<N: Number> Value<N> {
+    // 数値を取り出す。数値でなければ `ArithError::Type`
+    pub fn into_num(self) -> Result<N, ArithError> {
+        match self {
+            Value::Num(n) => Ok(n),
+            _ => Err(ArithError::Type),
+        }
+    }
+
+    // クォーテーションを取り出す。クォーテーションでなければ `ArithError::Type`
+    pub fn into_quote(self) -> Result<Quotation<N>, ArithError> {
+        match self {
+            Value::Quote(q) => Ok(q),
+            _ => Err(ArithError::Type),
+        }
+    }
+
+    // 変数名を取り出す。変数名でなければ `ArithError::Type`
+    pub fn into_symbol(self) -> Result<String, ArithError> {
+        match self {
+            Value::Symbol(name) => Ok(name),
+            _ => Err(ArithError::Type),
+        }
+    }
+
+    // ファイル名などに使う文字列を取り出す。変数名はその名前を返す。どちらでもなければ `ArithError::Type`
+    pub fn into_text(self) -> Result<String, ArithError> {
+        match self {
+            Value::Str(s) | Value::Symbol(s) => Ok(s),
+            _ => Err(ArithError::Type),
+        }
+    }
+
+    // 数値の場合は参照を返す
+    pub fn as_num(&self) -> Option<&N> {
+        match self {
+            Value::Num(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    // 出力用に整形する
+    pub fn format(&self, config: &Config) -> String {
+        match self {
+            Value::Num(n) => n.format(config),
+            Value::Quote(q) => q.format(config),
+            Value::Symbol(_) | Value::Str(_) => self.to_string(),
+        }
+    }
+}
+
+impl<N> From<N> for Value<N> {
+    fn from(n: N) -> Self {
+        Value::Num(n)
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Value<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Quote(q) => write!(f, "{}", q),
+            Value::Symbol(name) => write_symbol(f, name),
+            Value::Str(s) => write!(f, "{}", quote(s)),
+        }
+    }
+}
+
+impl<N: fmt::Debug> fmt::Debug for Value<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Num(n) => write!(f, "{:?}", n),
+            Value::Quote(q) => write!(f, "{:?}", q),
+            Value::Symbol(name) => write_symbol(f, name),
+            Value::Str(s) => write!(f, "{}", quote(s)),
+        }
+    }
+}
+
+// 変数名を読み直せる形で書く
+fn write_symbol(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if is_bare_symbol(name) {
+        write!(f, "'{}", name)
+    } else {
+        write!(f, "'{}", quote(name))
+    }
+}
+
+// クォーテーションの `[` の入れ子の深さの上限
+pub(crate) const MAX_NESTING_DEPTH: usize = 256;
+
+/**
+ * クォーテーション
+ *
+ * 評価を遅らせた項の並び。コンビネータに渡すプログラムとしても、`map` などで扱う値のリストとしても使う。
+ * 比較、表示、破棄は入れ子をたどって再帰するので、入れ子の深さは `MAX_NESTING_DEPTH` までに制限する
+ */
+#[derive(Clone, PartialEq)]
+pub struct Quotation<N> {
+    terms: Vec<Term<N>>,
+    // 自身を含めた `[` の入れ子の深さ。中にクォーテーションがなければ 1
+    depth: usize,
+}
+
+impl<N: Number> Quotation<N> {
+    // 項の並びから生成する。入れ子が深すぎる場合は `ArithError::NestingLimit`
+    pub fn new(terms: Vec<Term<N>>) -> Result<Self, ArithError> {
+        let quote = Self::program(terms);
+        if quote.depth > MAX_NESTING_DEPTH {
+            return Err(ArithError::NestingLimit);
+        }
+        Ok(quote)
+    }
+
+    // 式全体またはワードの本体として生成する。
+    // `[` で囲まないので、中の入れ子はパースの時点で制限済みとして深さを確かめない
+    pub(crate) fn program(terms: Vec<Term<N>>) -> Self {
+        let depth = terms
+            .iter()
+            .filter_map(|term| match term {
+                Term::Push(Value::Quote(q)) => Some(q.depth),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            + 1;
+        Self { terms, depth }
+    }
+
+    // 値を並べたリストを生成する。入れ子が深すぎる場合は `ArithError::NestingLimit`
+    pub fn from_values(values: Vec<Value<N>>) -> Result<Self, ArithError> {
+        Self::new(values.into_iter().map(Term::Push).collect())
+    }
+
+    // 項の並び
+    pub fn terms(&self) -> &[Term<N>] {
+        &self.terms
+    }
+
+    // リストとしての要素。ワードを含む場合は `ArithError::Type`
+    pub fn values(&self) -> Result<Vec<Value<N>>, ArithError> {
+        self.terms
+            .iter()
+            .map(|term| match term {
+                Term::Push(v) => Ok(v.clone()),
+                Term::Word { .. } => Err(ArithError::Type),
+            })
+            .collect()
+    }
+
+    // 出力用に整形する
+    fn format(&self, config: &Config) -> String {
+        let mut s = String::from("[");
+        for term in &self.terms {
+            s.push(' ');
+            match term {
+                Term::Push(v) => s.push_str(&v.format(config)),
+                Term::Word { name, .. } => s.push_str(name),
+            }
+        }
+        s.push_str(" ]");
+        s
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Quotation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for term in &self.terms {
+            write!(f, " {}", term)?;
+        }
+        write!(f, " ]")
+    }
+}
+
+impl<N: fmt::Debug> fmt::Debug for Quotation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for term in &self.terms {
+            write!(f, " {:?}", term)?;
+        }
+        write!(f, " ]")
+    }
+}
+
+/**
+ * クォーテーションの項
+ *
+ * ワードの位置はエラーの報告にだけ使うので、比較では名前だけを見る
+ */
+#[derive(Clone)]
+pub enum Term<N> {
+    // スタックに積む値
+    Push(Value<N>),
+    // 評価時に演算子を引く名前。`span` は式の中でのトークンの位置
+    Word { name: String, span: Span },
+}
+
+impl<N: PartialEq> PartialEq for Term<N> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Term::Push(a), Term::Push(b)) => a == b,
+            (Term::Word { name: a, .. }, Term::Word { name: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Term<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Push(v) => write!(f, "{}", v),
+            Term::Word { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+impl<N: fmt::Debug> fmt::Debug for Term<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Push(v) => write!(f, "{:?}", v),
+            Term::Word { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/**
+ * 標準入力に `input` を渡して rpncalc を実行する
+ */
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rpncalc"))
+        .args(args)
+        .arg("--color=never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // 引数の誤りでは入力を読まずに終了するので、書き込めなくてもよい
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_summary() {
+    // 空白だけの行は数えない
+    let output = run(&[], "1 2 +\n\n  \n3 4 *\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "3\n12\n");
+    assert_eq!(
+        stderr(&output),
+        "2 lines processed: 2 succeeded, 0 failed\n"
+    );
+
+    let output = run(&[], "1 1 +\n");
+    assert_eq!(stderr(&output), "1 line processed: 1 succeeded, 0 failed\n");
+}
+
+#[test]
+fn test_failed_lines() {
+    // 失敗した行を報告して次の行に進み、終了ステータスは 1
+    let output = run(&[], "1 2 +\n1 0 /\n2 3 *\nfoo\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "3\n6\n");
+    let errors = stderr(&output);
+    assert!(errors.contains("division by zero"));
+    assert!(errors.contains("<stdin>:2:5"));
+    assert!(errors.contains("<stdin>:4:1"));
+    assert!(errors.ends_with("4 lines processed: 2 succeeded, 2 failed\n"));
+}
+
+#[test]
+fn test_fail_fast() {
+    // 最初のエラーで読むのをやめる
+    let output = run(&["--fail-fast"], "1 2 +\n1 0 /\n2 3 *\nfoo\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "3\n");
+    let errors = stderr(&output);
+    assert!(errors.contains("<stdin>:2:5"));
+    assert!(!errors.contains("<stdin>:4:1"));
+    assert!(errors.ends_with("2 lines processed: 1 succeeded, 1 failed\n"));
+
+    // 失敗がなければすべての行を評価する
+    let output = run(&["--fail-fast"], "1 2 +\n2 3 *\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "3\n6\n");
+}
+
+#[test]
+fn test_input_error() {
+    // 入力を処理できなければ終了ステータスは 2 で、集計は出さない
+    let output = run(&["no/such/file.rpn"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "");
+    let errors = stderr(&output);
+    assert!(errors.contains("cannot open no/such/file.rpn"));
+    assert!(!errors.contains("processed"));
+}
+
+#[test]
+fn test_precision_limit() {
+    // 上限を超える桁数は引数の誤りとして拒否する
+    let output = run(&["--mode", "float", "--precision", "70000"], "1 3 /\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--precision"));
+
+    let output = run(
+        &[
+            "--mode",
+            "rational",
+            "--rational-format",
+            "decimal",
+            "--precision",
+            "1000",
+        ],
+        "1 3 /\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_scale_limit() {
+    // 上限を超える桁数は引数の誤りとして拒否する
+    for scale in ["70000", "1000000"] {
+        let output = run(&["--mode", "decimal", "--scale", scale], "1 3 /\n");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(stderr(&output).contains("--scale"));
+    }
+
+    let output = run(&["--mode", "decimal", "--scale", "1000"], "1 3 /\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output).trim().len(), "0.".len() + 1000);
+}
+
+#[test]
+fn test_load_session_with_settings() {
+    let path = std::env::temp_dir().join(format!("rpncalc-cli-{}.session", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let output = run(
+        &[
+            "--mode",
+            "float",
+            "--precision",
+            "2",
+            "--save-session",
+            path,
+        ],
+        "1 3 /\n",
+    );
+    assert_eq!(stdout(&output), "0.33\n");
+
+    // 明示した設定は読み込んだセッションの設定より優先する
+    let output = run(
+        &[
+            "--mode",
+            "float",
+            "--precision",
+            "4",
+            "--load-session",
+            path,
+        ],
+        "2 3 /\n",
+    );
+    assert_eq!(stdout(&output), "0.6667\n");
+
+    // 明示しなければセッションの設定を使う
+    let output = run(&["--mode", "float", "--load-session", path], "2 3 /\n");
+    assert_eq!(stdout(&output), "0.67\n");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_load_session_with_scale() {
+    let path =
+        std::env::temp_dir().join(format!("rpncalc-cli-scale-{}.session", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let output = run(
+        &["--mode", "decimal", "--scale", "2", "--save-session", path],
+        "1.5 'x sto\n",
+    );
+    assert!(output.status.success());
+
+    // 読み込んだ値も明示した桁数に揃える
+    let output = run(
+        &["--mode", "decimal", "--scale", "4", "--load-session", path],
+        "'x rcl\n'x rcl 1.5 =\n",
+    );
+    assert_eq!(stdout(&output), "1.5000\n1.0000\n");
+
+    std::fs::remove_file(path).unwrap();
+}